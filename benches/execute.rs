@@ -0,0 +1,20 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use chip_8::cpu::Chip8;
+
+// 1M `ADD V0, V1` through the lean execute path, without timers or bookkeeping.
+fn arithmetic_opcodes(c: &mut Criterion) {
+    let mut chip8 = Chip8::new();
+
+    c.bench_function("execute 1M arithmetic opcodes", |b| {
+        b.iter(|| {
+            for _ in 0..1_000_000 {
+                // keep pc from running off the end of memory
+                chip8.set_pc(0x200);
+                chip8.execute_opcode(black_box(0x8014));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, arithmetic_opcodes);
+criterion_main!(benches);
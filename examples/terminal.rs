@@ -0,0 +1,92 @@
+// Plays a ROM in the terminal: `cargo run --example terminal -- path/to/rom.ch8`.
+// The keypad is on the left of the keyboard (1234/QWER/ASDF/ZXCV), Esc quits.
+
+use std::error::Error;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+use std::{env, fs};
+
+use chip_8::cpu::{Chip8, ExecStatus};
+use chip_8::keyboard::Keypad;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::{cursor, execute, terminal};
+
+// terminals only report presses, so a key counts as held for this many frames.
+const KEY_HOLD_FRAMES: u32 = 6;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let path = env::args().nth(1).ok_or("usage: terminal <rom>")?;
+    let rom = fs::read(path)?;
+
+    let mut chip8 = Chip8::new();
+    chip8.load_into_memory(rom)?;
+
+    terminal::enable_raw_mode()?;
+    execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result = play(&mut chip8);
+    execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result?;
+
+    println!("{:?} after {} cycles", chip8.status(), chip8.cycles());
+    Ok(())
+}
+
+fn play(chip8: &mut Chip8) -> Result<(), Box<dyn Error>> {
+    let frame = Duration::from_secs(1) / 60;
+    let cycles_per_frame = (chip8.clock_hz() / 60).max(1) as usize;
+    let keypad = Keypad::default();
+    // frames left until each key gets released
+    let mut held = [0u32; 16];
+
+    loop {
+        let frame_start = Instant::now();
+
+        while event::poll(Duration::ZERO)? {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            match key.code {
+                KeyCode::Esc => return Ok(()),
+                KeyCode::Char(c) => {
+                    let c = c.to_ascii_uppercase();
+                    if let Some(hex) = (0..16).find(|&hex| keypad.char_for_hex(hex) == Some(c)) {
+                        chip8.key_down(hex);
+                        held[hex as usize] = KEY_HOLD_FRAMES;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        chip8.run_frame(cycles_per_frame);
+
+        for (hex, frames) in held.iter_mut().enumerate() {
+            if *frames > 0 {
+                *frames -= 1;
+                if *frames == 0 {
+                    chip8.key_up(hex as u8);
+                }
+            }
+        }
+
+        if chip8.take_redraw() {
+            let mut stdout = io::stdout();
+            execute!(stdout, cursor::MoveTo(0, 0))?;
+            // raw mode doesn't move back to the start of the line on its own.
+            write!(stdout, "{}", chip8.screen().to_string().replace('\n', "\r\n"))?;
+            stdout.flush()?;
+        }
+
+        if matches!(chip8.status(), ExecStatus::Halted | ExecStatus::Error(_)) {
+            return Ok(());
+        }
+
+        if let Some(remaining) = frame.checked_sub(frame_start.elapsed()) {
+            std::thread::sleep(remaining);
+        }
+    }
+}
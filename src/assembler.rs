@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+// Turns mnemonics (the same syntax produced by the disassembler) into big-endian opcode bytes.
+// Comments start with `;` and run until the end of the line.
+// Labels are declared on their own line (`loop:`) and can be used wherever an address is expected
+// (`JP loop`, `CALL draw`). They resolve to the address the program will have once loaded at 0x200.
+
+const LOWER_MEMORY_BOUNDARY: u16 = 512;
+
+#[derive(Debug, PartialEq)]
+pub struct AssembleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for AssembleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AssembleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        None
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Operand {
+    Register(u8),
+    Number(u16),
+    Label(String),
+    I,
+    IndirectI,   // [I]
+    DelayTimer,
+    SoundTimer,
+    Key,
+    Font,
+    BigFont,
+    Bcd,
+}
+
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssembleError> {
+    // first pass: find every label so forward references can be resolved.
+    let mut labels = HashMap::new();
+    // `None` once the program has reached the end of the 64KB address space
+    let mut address = Some(LOWER_MEMORY_BOUNDARY);
+
+    for (idx, line) in source.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let Some(current) = address else {
+            return Err(AssembleError { line: idx + 1, message: "program does not fit into memory".to_string() });
+        };
+        match parse_label(line) {
+            Some(label) => {
+                if labels.insert(label, current).is_some() {
+                    return Err(AssembleError { line: idx + 1, message: format!("duplicate label `{label}`") });
+                }
+            }
+            None => address = current.checked_add(2),
+        }
+    }
+
+    // second pass: encode the instructions.
+    let mut program = Vec::new();
+
+    for (idx, line) in source.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() || parse_label(line).is_some() {
+            continue;
+        }
+
+        let opcode = assemble_line(line, &labels).map_err(|message| AssembleError { line: idx + 1, message })?;
+        program.extend_from_slice(&opcode.to_be_bytes());
+    }
+    Ok(program)
+}
+
+fn parse_label(line: &str) -> Option<&str> {
+    line.strip_suffix(':').filter(|label| is_identifier(label))
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.split_once(';') {
+        Some((code, _)) => code,
+        None => line,
+    }
+}
+
+fn assemble_line(line: &str, labels: &HashMap<&str, u16>) -> Result<u16, String> {
+    let (mnemonic, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let mnemonic = mnemonic.to_uppercase();
+
+    let operands = rest
+        .split(',')
+        .map(str::trim)
+        .filter(|operand| !operand.is_empty())
+        .map(|operand| match parse_operand(operand)? {
+            Operand::Label(label) => match labels.get(label.as_str()) {
+                Some(address) => Ok(Operand::Number(*address)),
+                None => Err(format!("undefined label `{label}`")),
+            },
+            operand => Ok(operand),
+        })
+        .collect::<Result<Vec<Operand>, String>>()?;
+
+    use Operand::*;
+    let opcode = match (mnemonic.as_str(), operands.as_slice()) {
+        ("CLS", []) => 0x00E0,
+        ("RET", []) => 0x00EE,
+        ("SYS", [Number(nnn)]) => address(*nnn)?,
+        ("JP", [Number(nnn)]) => 0x1000 | address(*nnn)?,
+        ("CALL", [Number(nnn)]) => 0x2000 | address(*nnn)?,
+        ("SE", [Register(x), Number(nn)]) => 0x3000 | vx(*x) | byte(*nn)?,
+        ("SNE", [Register(x), Number(nn)]) => 0x4000 | vx(*x) | byte(*nn)?,
+        ("SE", [Register(x), Register(y)]) => 0x5000 | vx(*x) | vy(*y),
+        ("LD", [Register(x), Number(nn)]) => 0x6000 | vx(*x) | byte(*nn)?,
+        ("ADD", [Register(x), Number(nn)]) => 0x7000 | vx(*x) | byte(*nn)?,
+        ("LD", [Register(x), Register(y)]) => 0x8000 | vx(*x) | vy(*y),
+        ("OR", [Register(x), Register(y)]) => 0x8001 | vx(*x) | vy(*y),
+        ("AND", [Register(x), Register(y)]) => 0x8002 | vx(*x) | vy(*y),
+        ("XOR", [Register(x), Register(y)]) => 0x8003 | vx(*x) | vy(*y),
+        ("ADD", [Register(x), Register(y)]) => 0x8004 | vx(*x) | vy(*y),
+        ("SUB", [Register(x), Register(y)]) => 0x8005 | vx(*x) | vy(*y),
+        ("SHR", [Register(x)]) => 0x8006 | vx(*x),
+        ("SHR", [Register(x), Register(y)]) => 0x8006 | vx(*x) | vy(*y),
+        ("SUBN", [Register(x), Register(y)]) => 0x8007 | vx(*x) | vy(*y),
+        ("SHL", [Register(x)]) => 0x800E | vx(*x),
+        ("SHL", [Register(x), Register(y)]) => 0x800E | vx(*x) | vy(*y),
+        ("SNE", [Register(x), Register(y)]) => 0x9000 | vx(*x) | vy(*y),
+        ("LD", [I, Number(nnn)]) => 0xA000 | address(*nnn)?,
+        ("JP", [Register(0), Number(nnn)]) => 0xB000 | address(*nnn)?,
+        ("RND", [Register(x), Number(nn)]) => 0xC000 | vx(*x) | byte(*nn)?,
+        ("DRW", [Register(x), Register(y), Number(n)]) => 0xD000 | vx(*x) | vy(*y) | nibble(*n)?,
+        ("SKP", [Register(x)]) => 0xE09E | vx(*x),
+        ("SKNP", [Register(x)]) => 0xE0A1 | vx(*x),
+        ("LD", [Register(x), DelayTimer]) => 0xF007 | vx(*x),
+        ("LD", [Register(x), Key]) => 0xF00A | vx(*x),
+        ("LD", [DelayTimer, Register(x)]) => 0xF015 | vx(*x),
+        ("LD", [SoundTimer, Register(x)]) => 0xF018 | vx(*x),
+        ("ADD", [I, Register(x)]) => 0xF01E | vx(*x),
+        ("LD", [Font, Register(x)]) => 0xF029 | vx(*x),
+        ("LD", [BigFont, Register(x)]) => 0xF030 | vx(*x),
+        ("LD", [Bcd, Register(x)]) => 0xF033 | vx(*x),
+        ("LD", [IndirectI, Register(x)]) => 0xF055 | vx(*x),
+        ("LD", [Register(x), IndirectI]) => 0xF065 | vx(*x),
+        (
+            "CLS" | "RET" | "SYS" | "JP" | "CALL" | "SE" | "SNE" | "LD" | "ADD" | "OR" | "AND" | "XOR"
+            | "SUB" | "SHR" | "SUBN" | "SHL" | "RND" | "DRW" | "SKP" | "SKNP",
+            _
+        ) => return Err(format!("invalid operands for {mnemonic}: `{}`", rest.trim())),
+        _ => return Err(format!("unknown mnemonic `{mnemonic}`")),
+    };
+    Ok(opcode)
+}
+
+fn parse_operand(operand: &str) -> Result<Operand, String> {
+    let upper = operand.to_uppercase();
+    let parsed = match upper.as_str() {
+        "I" => Operand::I,
+        "[I]" => Operand::IndirectI,
+        "DT" => Operand::DelayTimer,
+        "ST" => Operand::SoundTimer,
+        "K" => Operand::Key,
+        "F" => Operand::Font,
+        "HF" => Operand::BigFont,
+        "B" => Operand::Bcd,
+        _ => {
+            if let Some(register) = upper.strip_prefix('V').filter(|register| register.len() == 1) {
+                u8::from_str_radix(register, 16)
+                    .map(Operand::Register)
+                    .map_err(|_| format!("invalid register `{operand}`"))?
+            } else if let Some(hex) = upper.strip_prefix("0X") {
+                u16::from_str_radix(hex, 16)
+                    .map(Operand::Number)
+                    .map_err(|_| format!("invalid number `{operand}`"))?
+            } else if is_identifier(operand) {
+                Operand::Label(operand.to_string())
+            } else {
+                upper
+                    .parse::<u16>()
+                    .map(Operand::Number)
+                    .map_err(|_| format!("invalid operand `{operand}`"))?
+            }
+        }
+    };
+    Ok(parsed)
+}
+
+fn vx(x: u8) -> u16 {
+    (x as u16) << 8
+}
+
+fn vy(y: u8) -> u16 {
+    (y as u16) << 4
+}
+
+fn address(nnn: u16) -> Result<u16, String> {
+    match nnn {
+        0..=0xFFF => Ok(nnn),
+        _ => Err(format!("address 0x{nnn:X} does not fit in 12 bits")),
+    }
+}
+
+fn byte(nn: u16) -> Result<u16, String> {
+    match nn {
+        0..=0xFF => Ok(nn),
+        _ => Err(format!("value 0x{nn:X} does not fit in a byte")),
+    }
+}
+
+fn nibble(n: u16) -> Result<u16, String> {
+    match n {
+        0..=0xF => Ok(n),
+        _ => Err(format!("value 0x{n:X} does not fit in a nibble")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disassembler::disassemble;
+
+    #[test]
+    fn test_assemble_instructions() {
+        let source = "
+            CLS
+            LD V4, 0xAA   ; load immediate
+            ADD V4, V5
+            JP 0x2DC
+            DRW V4, V6, 5
+        ";
+
+        let program = assemble(source).unwrap();
+
+        assert_eq!(program, vec![0x00, 0xE0, 0x64, 0xAA, 0x84, 0x54, 0x12, 0xDC, 0xD4, 0x65]);
+    }
+
+    #[test]
+    fn test_assemble_disassemble_round_trip() {
+        let instructions = vec![
+            "CLS",
+            "RET",
+            "LD V4, 0xAA",
+            "ADD V4, V5",
+            "JP 0x2DC",
+            "CALL 0x300",
+            "SE V1, 0x17",
+            "SNE V1, V2",
+            "LD I, 0x123",
+            "JP V0, 0x200",
+            "DRW V4, V6, 5",
+            "SKP VA",
+            "LD V3, K",
+            "LD HF, V3",
+            "LD DT, V3",
+            "ADD I, VF",
+            "LD [I], V4",
+            "LD V4, [I]",
+        ];
+
+        let program = assemble(&instructions.join("\n")).unwrap();
+
+        assert_eq!(disassemble(&program), instructions);
+    }
+
+    #[test]
+    fn test_assemble_backward_jump_to_label() {
+        let source = "
+            LD V0, 0
+        loop:
+            ADD V0, 1
+            SE V0, 10
+            JP loop
+            CLS
+        ";
+
+        let program = assemble(source).unwrap();
+
+        // `loop` is the second instruction, i.e. 0x200 + 2
+        assert_eq!(&program[6..8], &[0x12, 0x02]);
+    }
+
+    #[test]
+    fn test_assemble_forward_reference_to_label() {
+        let source = "
+            CALL draw
+            JP end
+        draw:
+            CLS
+            RET
+        end:
+            JP end
+        ";
+
+        let program = assemble(source).unwrap();
+
+        assert_eq!(&program[0..2], &[0x22, 0x04]);
+        assert_eq!(&program[2..4], &[0x12, 0x08]);
+        assert_eq!(&program[8..10], &[0x12, 0x08]);
+    }
+
+    #[test]
+    fn test_assemble_undefined_label() {
+        let error = assemble("CLS\nJP nowhere").unwrap_err();
+
+        assert_eq!(error.line, 2);
+        assert!(error.message.contains("nowhere"));
+    }
+
+    #[test]
+    fn test_assemble_duplicate_label() {
+        let error = assemble("start:\nCLS\nstart:\nRET").unwrap_err();
+
+        assert_eq!(error.line, 3);
+        assert!(error.message.contains("start"));
+    }
+
+    #[test]
+    fn test_assemble_program_larger_than_memory() {
+        // exactly fills memory from 0x200 to 0xFFFF
+        let instructions = (0x10000 - LOWER_MEMORY_BOUNDARY as usize) / 2;
+        let source = "CLS\n".repeat(instructions);
+
+        assert_eq!(assemble(&source).unwrap().len(), instructions * 2);
+
+        let error = assemble(&(source.clone() + "RET")).unwrap_err();
+        assert_eq!(error.line, instructions + 1);
+        assert!(error.message.contains("memory"));
+
+        let error = assemble(&(source + "end:")).unwrap_err();
+        assert_eq!(error.line, instructions + 1);
+    }
+
+    #[test]
+    fn test_assemble_unknown_mnemonic() {
+        let error = assemble("CLS\nJUMP 0x200").unwrap_err();
+
+        assert_eq!(error.line, 2);
+        assert!(error.message.contains("JUMP"));
+    }
+
+    #[test]
+    fn test_assemble_bad_operand() {
+        let error = assemble("LD V4, 0x1AA").unwrap_err();
+        assert_eq!(error.line, 1);
+
+        let error = assemble("\n\nADD VG, V1").unwrap_err();
+        assert_eq!(error.line, 3);
+
+        let error = assemble("DRW V4, V6").unwrap_err();
+        assert_eq!(error.line, 1);
+        assert_eq!(error.to_string(), "line 1: invalid operands for DRW: `V4, V6`");
+    }
+}
@@ -1,12 +1,42 @@
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+#[cfg(feature = "std")]
 use std::error::Error;
-use rand::random;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::time::Instant;
+use crate::disassembler::{disassemble_opcode, instruction_kind};
+use crate::error::Chip8Error;
+use crate::instruction::{decode, Instruction};
 use crate::memory::Memory;
-use crate::screen::Screen;
-use crate::keyboard::Keypad;
+use crate::quirks::{Platform, Quirks};
+use crate::screen::{Screen, HIRES_HEIGHT};
+use crate::keyboard::{InputSource, Keypad};
 
 const REGISTER: usize = 16;
 const STACK: usize = 16;
 const LOWER_MEMORY_BOUNDARY: u16 = 512;
+const FONTSET_START: u16 = 0x0;
+const BIG_FONTSET_START: u16 = 0xA0;   // where SUPER-CHIP interpreters conventionally keep it
+// instructions per second, 10 per 60Hz frame
+const DEFAULT_CLOCK_HZ: u32 = 600;
+// `advance` executes at most this fraction of a second worth of instructions at once
+const MAX_CATCH_UP_FRACTION: u32 = 4;
+#[cfg(feature = "std")]
+const SAVE_STATE_MAGIC: &[u8; 4] = b"C8SS";
+#[cfg(feature = "std")]
+const SAVE_STATE_VERSION: u8 = 2;
 
 static CHIP8_FONTSET: [u8; 80] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
@@ -27,8 +57,144 @@ static CHIP8_FONTSET: [u8; 80] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
 
+// SUPER-CHIP 8x10 font for the digits 0-9, used by FX30.
+static SCHIP_BIG_FONTSET: [u8; 100] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+];
+
+// Snapshot of everything an instruction can modify, used to rewind execution.
+// Peripherals state that is driven from the outside (the keypad) is not part of it.
+#[derive(Clone)]
+pub struct Chip8State {
+    memory: Memory,
+    pc: u16,
+    i: u16,
+    register: [u8; REGISTER],
+    stack: [u16; STACK],
+    sp: usize,
+    delay_timer: u8,
+    sound_timer: u8,
+    cycle_count: u64,
+    beep_count: u64,
+    status: ExecStatus,
+    screen: Screen,
+}
+
+#[cfg(feature = "std")]
+fn read_u16<R: Read>(r: &mut R) -> io::Result<u16> {
+    let mut bytes = [0; 2];
+    r.read_exact(&mut bytes)?;
+    Ok(u16::from_be_bytes(bytes))
+}
+
+// Receives (x, y, lit) for every pixel that changed, see `Chip8::set_pixel_callback`.
+pub type PixelCallback = Box<dyn FnMut(usize, usize, bool) + Send>;
+
+// Stops a running `Chip8::run` from another thread.
+#[derive(Clone)]
+pub struct HaltHandle {
+    halt: Arc<AtomicBool>,
+}
+
+impl HaltHandle {
+    pub fn request_halt(&self) {
+        self.halt.store(true, Ordering::SeqCst);
+    }
+}
+
+// What makes `run` and friends consider the program finished.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HaltMode {
+    // a 0x0000 word, the historical behavior of this interpreter
+    #[default]
+    OnZeroWord,
+    // run until halted from the outside, 0x0000 is executed as a `SYS 0` no-op
+    Never,
+    // a 1NNN jumping to its own address, the way most ROMs signal they are done
+    OnInfiniteJump,
+}
+
+// Why the machine is (not) making progress, see `Chip8::status`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExecStatus {
+    Running,
+    // reached the end of the program according to the `HaltMode`
+    Halted,
+    // stopped at an instruction that can't be executed, pc still points at it. Execution never panics,
+    // stack overflows and underflows, unknown opcodes and out of bounds accesses all end up here.
+    Error(Chip8Error),
+    // parked on FX0A until a key gets pressed
+    WaitingForKey,
+    // suspended with `Chip8::pause`, nothing gets executed until `resume`
+    Paused,
+}
+
+#[derive(Default)]
+pub struct Chip8Builder {
+    quirks: Quirks,
+    halt_mode: HaltMode,
+    clock_hz: Option<u32>,
+    platform: Option<Platform>,
+    lenient: bool,
+}
+
+impl Chip8Builder {
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    pub fn halt_mode(mut self, halt_mode: HaltMode) -> Self {
+        self.halt_mode = halt_mode;
+        self
+    }
+
+    // See `Chip8::set_strict`, machines are strict by default.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.lenient = !strict;
+        self
+    }
+
+    // Uses the quirks and, unless `clock_hz` is given, the clock speed of `platform`.
+    // XO-CHIP machines also get the 64KB of memory `F000 NNNN` can address.
+    pub fn platform(mut self, platform: Platform) -> Self {
+        self.quirks = platform.quirks();
+        self.platform = Some(platform);
+        self
+    }
+
+    // How many instructions `run_realtime` executes per second.
+    pub fn clock_hz(mut self, clock_hz: u32) -> Self {
+        self.clock_hz = Some(clock_hz);
+        self
+    }
+
+    pub fn build(self) -> Chip8 {
+        let mut chip8 = match self.platform {
+            Some(Platform::XoChip) => Chip8::new_with_memory(0x10000),
+            _ => Chip8::new(),
+        };
+        chip8.quirks = self.quirks;
+        chip8.halt_mode = self.halt_mode;
+        chip8.strict = !self.lenient;
+        if let Some(clock_hz) = self.clock_hz.or(self.platform.map(Platform::default_clock_hz)) {
+            chip8.clock_hz = clock_hz;
+        }
+        chip8
+    }
+}
+
 pub struct Chip8 {
-    memory: Memory,                 // 4KB of memory
+    memory: Memory,                 // 4KB of memory (64KB with `xo-chip`) unless built with `new_with_memory`
     pc: u16,                      // program counter
     i: u16,                       // index register
     register: [u8; REGISTER],      // register of size 16
@@ -36,6 +202,24 @@ pub struct Chip8 {
     sp: usize,                      // stack pointer
     delay_timer: u8,
     sound_timer: u8,
+    beep_count: u64,                // times the sound timer got started from 0
+    cycle_count: u64,               // number of executed instructions
+    halt: Arc<AtomicBool>,          // set to stop the `run` loop
+    quirks: Quirks,
+    halt_mode: HaltMode,
+    clock_hz: u32,
+    strict: bool,                   // 5XYN and 9XYN with N other than 0 are unknown opcodes
+    rom: (u16, u16),                // start and length of the loaded program
+    status: ExecStatus,
+    paused: bool,
+    waiting_for_vblank: bool,       // a DXYN ended the current frame (display wait quirk)
+    drew_in_frame: bool,            // a DXYN ran since the current frame started
+    beeped_in_frame: bool,          // the sound timer was running at some point of the current frame
+    in_frame: bool,                 // timers tick once at the end of `run_frame` instead of per instruction
+    key_snapshot: Option<u16>,      // keys held when the current `run_frame` started, bit N for key N
+    // time passed to `advance` that didn't add up to a whole instruction or frame yet
+    cycle_carry: Duration,
+    timer_carry: Duration,
 
     // ** Peripherals **
 
@@ -44,15 +228,64 @@ pub struct Chip8 {
 
     // Keyboard
     keyboard: Keypad,
+    // replaces the keypad as the source of input when set
+    input: Option<Box<dyn InputSource + Send>>,
+    // called with NNN for every 0NNN SYS instruction
+    sys_hook: Option<Box<dyn FnMut(u16) + Send>>,
+    // called with (x, y, lit) for every pixel 00E0 or DXYN flipped
+    pixel_callback: Option<PixelCallback>,
+    // called with I for every DXYN reading below 0x200, debug builds only
+    low_sprite_warning: Option<Box<dyn FnMut(u16) + Send>>,
+
+    // ** Debugging **
+
+    // snapshots taken before each `step`, most recent last
+    history: VecDeque<Chip8State>,
+    history_capacity: usize,
+    breakpoints: BTreeSet<u16>,
+    // executions per instruction form, only counted when enabled
+    opcode_histogram: Option<BTreeMap<&'static str, u64>>,
+
+    // (cycle, key, pressed) input events
+    recording: Option<Vec<(u64, u8, bool)>>,
+    replay_events: VecDeque<(u64, u8, bool)>,
+    watched_registers: BTreeSet<usize>,
+    watch_hit: Option<usize>,       // watched register changed by the last step
+
+    // xorshift state for RND, there is no `rand` without std
+    #[cfg(not(feature = "std"))]
+    rng_state: u32,
+}
+
+impl Default for Chip8 {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Chip8 {
     pub fn new() -> Self {
-        let mut memory =  Memory::default();
-        for i in 0..79 {
-            memory[i] = CHIP8_FONTSET[i as usize];
-        }
+        Self::with_memory(Memory::default()).with_fonts()
+    }
 
+    // Like `new`, but memory is all zeroes: no font is installed, e.g. before `set_font` with a custom one.
+    pub fn new_blank() -> Self {
+        Self::with_memory(Memory::default())
+    }
+
+    // A machine with `size` bytes of memory instead of the default 4KB, e.g. 65536 for XO-CHIP.
+    pub fn new_with_memory(size: usize) -> Self {
+        Self::with_memory(Memory::with_size(size)).with_fonts()
+    }
+
+    fn with_fonts(mut self) -> Self {
+        // memory is never smaller than the interpreter area the fonts live in
+        self.memory.load_fontset(&CHIP8_FONTSET, FONTSET_START).expect("the small font fits into memory");
+        self.memory.load_fontset(&SCHIP_BIG_FONTSET, BIG_FONTSET_START).expect("the big font fits into memory");
+        self
+    }
+
+    fn with_memory(memory: Memory) -> Self {
         Chip8 {
             memory,
             i: 0,
@@ -62,27 +295,281 @@ impl Chip8 {
             sp: 0,
             delay_timer: 0,
             sound_timer: 0,
+            beep_count: 0,
+            cycle_count: 0,
+            halt: Arc::new(AtomicBool::new(false)),
+            quirks: Quirks::default(),
+            halt_mode: HaltMode::default(),
+            clock_hz: DEFAULT_CLOCK_HZ,
+            strict: true,
+            rom: (LOWER_MEMORY_BOUNDARY, 0),
+            status: ExecStatus::Running,
+            paused: false,
+            waiting_for_vblank: false,
+            drew_in_frame: false,
+            beeped_in_frame: false,
+            in_frame: false,
+            key_snapshot: None,
+            cycle_carry: Duration::ZERO,
+            timer_carry: Duration::ZERO,
             screen: Screen::default(),
             keyboard: Keypad::default(),
+            input: None,
+            sys_hook: None,
+            pixel_callback: None,
+            low_sprite_warning: None,
+            history: VecDeque::new(),
+            history_capacity: 0,
+            breakpoints: BTreeSet::new(),
+            opcode_histogram: None,
+            recording: None,
+            replay_events: VecDeque::new(),
+            watched_registers: BTreeSet::new(),
+            watch_hit: None,
+            #[cfg(not(feature = "std"))]
+            rng_state: 0x2545_F491,
         }
     }
 
+    pub fn builder() -> Chip8Builder {
+        Chip8Builder::default()
+    }
+
+    // A machine set up with the quirks, clock speed and memory size of `platform` with `rom` loaded.
+    pub fn new_for_rom(rom: &[u8], platform: Platform) -> Result<Chip8, Chip8Error> {
+        let mut chip8 = Chip8::builder().platform(platform).build();
+        chip8.load_into_memory(rom)?;
+        Ok(chip8)
+    }
+
+    // Builds a fresh machine, loads the ROM at `path` and runs it until it halts.
+    #[cfg(feature = "std")]
+    pub fn load_and_run_rom<P: AsRef<Path>>(path: P) -> Result<Chip8, Box<dyn Error>> {
+        let program = fs::read(path)?;
+
+        let mut chip8 = Chip8::new();
+        chip8.load_into_memory(program)?;
+        chip8.run();
+
+        Ok(chip8)
+    }
+
+    // Sets V0, V1, ... to `data` in order. Values past the 16th are ignored.
     pub fn populate_register(&mut self, data: Vec<u8>) {
-        for (idx, value) in data.iter().enumerate() {
-            self.register[idx] = *value
+        for (register, value) in self.register.iter_mut().zip(data) {
+            *register = value
+        }
+    }
+
+    // Sets the given (register, value) pairs, leaving the other registers alone.
+    // Nothing is set if one of the registers doesn't exist.
+    pub fn set_registers(&mut self, values: &[(usize, u8)]) -> Result<(), Chip8Error> {
+        if let Some(&(index, _)) = values.iter().find(|&&(index, _)| index >= REGISTER) {
+            return Err(Chip8Error::InvalidRegister(index));
+        }
+
+        for &(index, value) in values {
+            self.register[index] = value;
+        }
+        Ok(())
+    }
+
+    /// Loads a ROM at 0x200 straight from a byte slice, e.g. one built into the binary.
+    ///
+    /// ```
+    /// use chip_8::cpu::Chip8;
+    ///
+    /// // stands in for `include_bytes!("game.ch8")`
+    /// const ROM: &[u8] = &[0x60, 0x2A];
+    ///
+    /// let mut chip8 = Chip8::new();
+    /// chip8.load_rom(ROM).unwrap();
+    /// chip8.run();
+    ///
+    /// assert_eq!(chip8.get_value_at_register_addr(0), Some(0x2A));
+    /// ```
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), Chip8Error> {
+        self.load_into_memory(rom)
+    }
+
+    // Accepts anything byte-like, e.g. a `Vec<u8>` read from a file or a borrowed `&[u8]`.
+    pub fn load_into_memory(&mut self, program: impl AsRef<[u8]>) -> Result<(), Chip8Error> {
+        let program = program.as_ref();
+        self.memory.load_program(program)?;
+        self.rom = (LOWER_MEMORY_BOUNDARY, program.len() as u16);
+        Ok(())
+    }
+
+    // Swaps in a new build of the program for live coding: memory from 0x200 up is cleared and `program`
+    // loaded there, execution restarts at 0x200 with an empty stack. The display, registers, timers and
+    // fonts are kept, the step history and watch hit of the old program are dropped.
+    // Nothing changes if the program doesn't fit.
+    pub fn reload_program(&mut self, program: &[u8]) -> Result<(), Chip8Error> {
+        let mut memory = self.memory.clone();
+        memory.clear_from(LOWER_MEMORY_BOUNDARY);
+        memory.load_program(program)?;
+
+        self.memory = memory;
+        self.rom = (LOWER_MEMORY_BOUNDARY, program.len() as u16);
+        self.pc = LOWER_MEMORY_BOUNDARY;
+        self.sp = 0;
+        self.status = ExecStatus::Running;
+        self.history.clear();
+        self.watch_hit = None;
+        Ok(())
+    }
+
+    // Replaces the built-in font; `font` holds the 5-byte sprites for the digits 0-F.
+    // Anything but 80 bytes is rejected, a longer font would overwrite the big one.
+    pub fn set_font(&mut self, font: &[u8]) -> Result<(), Chip8Error> {
+        if font.len() != CHIP8_FONTSET.len() {
+            return Err(Chip8Error::InvalidFontLength(font.len()));
+        }
+
+        self.memory.load_fontset(font, FONTSET_START)
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    pub fn halt_mode(&self) -> HaltMode {
+        self.halt_mode
+    }
+
+    // whether executing `opcode` at the current pc would end the program.
+    fn is_halt_opcode(&self, opcode: u16) -> bool {
+        match self.halt_mode {
+            HaltMode::OnZeroWord => opcode == 0x0,
+            HaltMode::Never => false,
+            // 1NNN only reaches the first 4KB, above that no jump can target its own address
+            HaltMode::OnInfiniteJump => self.pc <= 0x0FFF && opcode == 0x1000 | self.pc,
+        }
+    }
+
+    // Writes the data records of an Intel HEX file to their addresses. Nothing is written
+    // if any record is malformed or doesn't fit into memory.
+    pub fn load_ihex(&mut self, hex: &str) -> Result<(), Chip8Error> {
+        let records = crate::ihex::parse(hex)?;
+
+        let mut memory = self.memory.clone();
+        for (addr, data) in records {
+            memory.write_sprite(addr, &data)?;
+        }
+        self.memory = memory;
+        Ok(())
+    }
+
+    // Loads a program written out as hex, e.g. "60 0A D0 15" or "600A,D015". Whitespace and commas
+    // between the digits are ignored, but every byte needs both of its digits.
+    pub fn load_hex_string(&mut self, hex: &str) -> Result<(), Chip8Error> {
+        let mut digits = Vec::new();
+        for (offset, c) in hex.char_indices() {
+            if c.is_whitespace() || c == ',' {
+                continue;
+            }
+            let digit = c.to_digit(16).ok_or(Chip8Error::InvalidHexString { offset, reason: "not a hex digit" })?;
+            digits.push(digit as u8);
+        }
+        if !digits.len().is_multiple_of(2) {
+            return Err(Chip8Error::InvalidHexString { offset: hex.len(), reason: "odd number of hex digits" });
+        }
+
+        let program: Vec<u8> = digits.chunks(2).map(|pair| pair[0] << 4 | pair[1]).collect();
+        self.load_into_memory(program)
+    }
+
+    // Writes each (address, bytes) segment to its address, e.g. a launcher ROM and the game it starts.
+    // Nothing is written if a segment doesn't fit into memory or overlaps another one.
+    pub fn load_segments(&mut self, segments: &[(u16, &[u8])]) -> Result<(), Chip8Error> {
+        let mut ranges: Vec<(usize, usize)> = segments
+            .iter()
+            .map(|&(addr, bytes)| (addr as usize, addr as usize + bytes.len()))
+            .collect();
+        ranges.sort_unstable();
+        if let Some(pair) = ranges.windows(2).find(|pair| pair[0].1 > pair[1].0) {
+            return Err(Chip8Error::OverlappingSegments { addr: pair[1].0 as u16 });
+        }
+
+        let mut memory = self.memory.clone();
+        for &(addr, bytes) in segments {
+            memory.write_sprite(addr, bytes)?;
         }
+        self.memory = memory;
+        Ok(())
+    }
+
+    // Loads the program at `start` and points the program counter at it,
+    // for ROMs that don't expect to be loaded at 0x200 (e.g. ETI-660 at 0x600).
+    pub fn load_into_memory_at(&mut self, program: impl AsRef<[u8]>, start: u16) -> Result<(), Chip8Error> {
+        let program = program.as_ref();
+        self.memory.load_program_at(program, start)?;
+        self.rom = (start, program.len() as u16);
+        self.pc = start;
+        Ok(())
+    }
+    // Read-only view of memory, e.g. for a debugger hexdump.
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn screen(&self) -> &Screen {
+        &self.screen
+    }
+
+    pub fn read_byte(&self, addr: u16) -> Option<u8> {
+        self.memory.read_byte(addr)
     }
 
-    pub fn load_into_memory(&mut self, program: Vec<u8>) -> Result<(), Box<dyn Error>> {
-        self.memory.load_program(program)
+    // Checksum of the memory the last program was loaded into, a stable ID for the current ROM
+    // as long as it doesn't modify itself.
+    pub fn rom_checksum(&self) -> u32 {
+        let (start, len) = self.rom;
+        self.memory.checksum(start, len)
     }
+
+    pub fn write_sprite(&mut self, addr: u16, bytes: &[u8]) -> Result<(), Chip8Error> {
+        self.memory.write_sprite(addr, bytes)
+    }
+
     pub fn get_value_at_register_addr(&self, addr: u8) -> Option<u8> {
         self.register.get(addr as usize).copied()
     }
 
+    pub fn set_pc(&mut self, addr: u16) {
+        self.pc = addr;
+    }
+
+    /// Points the index register at `addr`, e.g. to draw a sprite placed in memory by hand.
+    ///
+    /// ```
+    /// use chip_8::cpu::Chip8;
+    ///
+    /// let mut chip8 = Chip8::new();
+    /// // DRW V0, V0, 1 twice, the second draw erases the first one.
+    /// chip8.load_into_memory(vec![0xD0, 0x01, 0xD0, 0x01]).unwrap();
+    /// // a sprite of a single row of 8 pixels.
+    /// chip8.load_into_memory_at(vec![0xFF], 0x300).unwrap();
+    ///
+    /// chip8.set_index_register(0x300);
+    /// chip8.set_pc(0x200);
+    /// chip8.step();
+    /// chip8.step();
+    ///
+    /// assert_eq!(chip8.get_value_at_register_addr(0xF), Some(1));
+    /// ```
+    pub fn set_index_register(&mut self, addr: u16) {
+        self.i = addr;
+    }
+
+    // Bytes past the end of memory read as 0.
     fn read_opcode(&self) -> u16 {
-        let high_byte = self.memory[self.pc] as u16;
-        let low_byte = self.memory[self.pc + 1] as u16;
+        let high_byte = self.memory.read_byte(self.pc).unwrap_or(0) as u16;
+        let low_byte = self.memory.read_byte(self.pc.wrapping_add(1)).unwrap_or(0) as u16;
 
         /*
         since opcodes take up 2 bytes of memory, and each element within our memory only has 1 byte of information,
@@ -96,27 +583,226 @@ impl Chip8 {
         high_byte << 8 | low_byte
     }
 
-    fn call_fn_at_addr(&mut self, addr: u16) {
-        let sp = self.sp;
-        let stack = self.stack;
-        if sp > stack.len() {
-            panic!("Stack overflow!");
+    // Press or release a key on the keypad.
+    pub fn set_key(&mut self, key: u8, pressed: bool) {
+        if let Some(recording) = self.recording.as_mut() {
+            recording.push((self.cycle_count, key, pressed));
+        }
+
+        if pressed {
+            self.keyboard.keypress(key);
+        } else {
+            self.keyboard.remove_keypress(key);
+        }
+    }
+
+    /// Presses `key` (0x0-0xF) on the keypad until `key_up` or `clear_keys` is called.
+    ///
+    /// ```
+    /// use chip_8::cpu::Chip8;
+    ///
+    /// let mut chip8 = Chip8::new();
+    /// // V0 = 5, SKP V0, V1 = 1
+    /// chip8.load_into_memory(vec![0x60, 0x05, 0xE0, 0x9E, 0x61, 0x01]).unwrap();
+    ///
+    /// chip8.key_down(5);
+    /// chip8.step();
+    /// chip8.step();
+    /// chip8.step();
+    ///
+    /// assert_eq!(chip8.get_value_at_register_addr(1), Some(0));
+    /// ```
+    pub fn key_down(&mut self, key: u8) {
+        self.set_key(key, true);
+    }
+
+    pub fn key_up(&mut self, key: u8) {
+        self.set_key(key, false);
+    }
+
+    // Releases every key, e.g. when the window loses focus.
+    pub fn clear_keys(&mut self) {
+        self.keyboard.zero_keypresses();
+    }
+
+    // Log every `set_key` call along with the cycle it happened at, so the session can be replayed.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    // Stops recording and returns the recorded (cycle, key, pressed) events.
+    pub fn take_recording(&mut self) -> Vec<(u64, u8, bool)> {
+        self.recording.take().unwrap_or_default()
+    }
+
+    // Injects each (cycle, key, pressed) event right before the instruction at that cycle executes.
+    pub fn replay(&mut self, events: Vec<(u64, u8, bool)>) {
+        self.replay_events = events.into();
+    }
+
+    fn inject_replay_events(&mut self) {
+        while let Some(&(cycle, key, pressed)) = self.replay_events.front() {
+            if cycle > self.cycle_count {
+                break;
+            }
+            self.replay_events.pop_front();
+            self.set_key(key, pressed);
+        }
+    }
+
+    // Read key presses from `input` instead of the built-in keypad.
+    pub fn set_input_source(&mut self, input: Box<dyn InputSource + Send>) {
+        self.input = Some(input);
+    }
+
+    // Handle 0NNN SYS instructions with `hook`, e.g. to emulate routines of the host machine.
+    // It gets called with NNN, execution continues with the next instruction afterwards.
+    pub fn set_sys_hook(&mut self, hook: Box<dyn FnMut(u16) + Send>) {
+        self.sys_hook = Some(hook);
+    }
+
+    // Report every pixel 00E0 and DXYN turn on or off to `callback` as (x, y, lit),
+    // e.g. to only update the changed texels of a texture.
+    pub fn set_pixel_callback(&mut self, callback: PixelCallback) {
+        self.pixel_callback = Some(callback);
+    }
+
+    // In debug builds `warning` gets called with I whenever DXYN draws a sprite from below 0x200.
+    // Drawing font characters is fine, but more often than not it means I was never set.
+    pub fn set_low_sprite_warning(&mut self, warning: Box<dyn FnMut(u16) + Send>) {
+        self.low_sprite_warning = Some(warning);
+    }
+
+    fn report_flipped_pixels(&mut self, before: [u128; HIRES_HEIGHT]) {
+        let Some(callback) = self.pixel_callback.as_mut() else {
+            return;
+        };
+        for (y, (old, new)) in before.iter().zip(self.screen.lit_rows()).enumerate() {
+            let mut flipped = old ^ new;
+            while flipped != 0 {
+                let x = flipped.leading_zeros() as usize;
+                let bit = 1 << (127 - x);
+                callback(x, y, new & bit != 0);
+                flipped &= !bit;
+            }
+        }
+    }
+
+    fn input_source(&mut self) -> &mut dyn InputSource {
+        match self.input.as_deref_mut() {
+            Some(input) => input,
+            None => &mut self.keyboard,
+        }
+    }
+
+    // The keys held right now, bit N is set while key N is pressed.
+    pub fn keys_snapshot(&mut self) -> u16 {
+        let input = self.input_source();
+        (0..16).filter(|&key| input.is_pressed(key)).fold(0, |keys, key| keys | 1 << key)
+    }
+
+    // Within `run_frame` keys are read from the snapshot taken at the start of the frame,
+    // so every instruction of a frame sees the same input.
+    fn is_key_pressed(&mut self, key: u8) -> bool {
+        match self.key_snapshot {
+            Some(keys) => key < 16 && keys & (1 << key) != 0,
+            None => self.input_source().is_pressed(key),
+        }
+    }
+
+    // The opcode that will be executed next, without executing it.
+    pub fn peek_opcode(&self) -> u16 {
+        self.read_opcode()
+    }
+
+    // The mnemonic of the instruction at pc, e.g. `LD V4, 0xAA`, or `DB 0xNNNN` if it is none.
+    // XO-CHIP's `F000 NNNN` shows the address it loads, e.g. `LD I, 0x1234`.
+    pub fn current_instruction_text(&self) -> String {
+        let opcode = self.read_opcode();
+        if opcode == 0xF000 {
+            let address_word = self.pc.wrapping_add(2);
+            if let (Some(high), Some(low)) = (self.read_byte(address_word), self.read_byte(address_word.wrapping_add(1))) {
+                return format!("LD I, 0x{:04X}", u16::from_be_bytes([high, low]));
+            }
+        }
+        disassemble_opcode(opcode)
+    }
+
+    fn call_fn_at_addr(&mut self, addr: u16) -> Result<(), Chip8Error> {
+        if self.sp >= self.stack.len() {
+            return Err(Chip8Error::StackOverflow);
         }
 
+        log::debug!("call {:#05X} from {:#05X}", addr, self.pc);
         self.stack[self.sp] = self.pc;
         self.sp += 1;
         self.pc = addr;
+        Ok(())
     }
 
-    fn return_from_fn_call(&mut self) {
+    fn return_from_fn_call(&mut self) -> Result<(), Chip8Error> {
         if self.sp == 0 {
-            panic!("Stack underflow!");
+            return Err(Chip8Error::StackUnderflow);
+        }
+        let addr = self.stack[self.sp - 1];
+        // execution continues after the call, that instruction has to be in memory.
+        if addr as usize + 3 >= self.memory.size() {
+            return Err(Chip8Error::InvalidReturnAddress(addr));
         }
         self.sp -= 1;
-        self.pc = self.stack[self.sp];
+        self.pc = addr;
+        log::debug!("return to {:#05X}", self.pc);
+        Ok(())
+    }
+
+    // The return addresses of the active calls, innermost last, e.g. for a debugger's call stack view.
+    pub fn call_stack(&self) -> &[u16] {
+        &self.stack[..self.sp]
+    }
+
+    // Replaces the call stack, innermost call last. Fails if there are more entries than stack levels.
+    pub fn set_call_stack(&mut self, entries: &[u16]) -> Result<(), Chip8Error> {
+        if entries.len() > STACK {
+            return Err(Chip8Error::StackOverflow);
+        }
+
+        self.stack = [0; STACK];
+        self.stack[..entries.len()].copy_from_slice(entries);
+        self.sp = entries.len();
+        Ok(())
+    }
+
+    // Whether `len` bytes starting at `addr` are in memory, flagging the instruction as failed if not.
+    fn check_memory_range(&mut self, addr: u16, len: usize) -> bool {
+        if addr as usize + len > self.memory.size() {
+            self.status = ExecStatus::Error(Chip8Error::AddressOutOfBounds { addr, len });
+            return false;
+        }
+        true
     }
 
     pub fn execute_instruction(&mut self, opcode: u16) {
+            self.inject_replay_events();
+            self.cycle_count += 1;
+            log::trace!("{:#06X} at {:#05X}", opcode, self.pc);
+
+            if let (Some(histogram), Some(kind)) = (self.opcode_histogram.as_mut(), instruction_kind(opcode)) {
+                *histogram.entry(kind).or_insert(0) += 1;
+            }
+
+            self.execute_opcode(opcode);
+
+            // Timers are updated every iteration at 60Hz, `run_frame` updates them once per frame instead.
+            if !self.in_frame {
+                self.tick_timers();
+            }
+    }
+
+    // Decodes and executes `opcode` only: no timers, cycle counting, tracing or input replay.
+    // Meant for benchmarks and front-ends that drive the timers themselves.
+    pub fn execute_opcode(&mut self, opcode: u16) {
+            self.status = ExecStatus::Running;
+
             let opcode_group = ((opcode & 0xF000) >> 12) as u8;
             let x = ((opcode & 0x0F00) >> 8) as usize;
             let y = ((opcode & 0x00F0) >> 4) as usize;
@@ -124,42 +810,62 @@ impl Chip8 {
             let nnn = opcode & 0x0FFF;
             let nn = (opcode & 0x00FF) as u8;
 
+            // only worth a copy of the screen if someone wants to know what changed.
+            let screen_before = (self.pixel_callback.is_some() && matches!(opcode_group, 0x0 | 0xD))
+                .then(|| self.screen.lit_rows());
+
             match (opcode_group, x, y, n) {
-                (0, 0, 0, 0) => {
+                (0, 0, 0, 0) if self.halt_mode == HaltMode::OnZeroWord => {
+                    self.status = ExecStatus::Halted;
                     return
                 },
                 // 0x00E0
                 (0x0, 0x0, 0xE, 0x0) => {
                     self.screen.clear_screen();
+                    if let Some(before) = screen_before {
+                        self.report_flipped_pixels(before);
+                    }
                 }
                 (0, 0, 0xE, 0xE) => {
-                    self.return_from_fn_call();
+                    if let Err(error) = self.return_from_fn_call() {
+                        self.status = ExecStatus::Error(error);
+                        return
+                    }
                 },
+                // 0NNN: SYS addr, calls a machine code routine on the original hardware.
+                // A no-op unless a hook emulating the routines is set.
+                (0x0, _, _, _) => {
+                    if let Some(hook) = self.sys_hook.as_mut() {
+                        hook(nnn);
+                    }
+                }
                 (0x1, _, _, _) => {
                     self.set_pc_to_addr(nnn);
                     return
                 },
                 (0x2, _, _, _) => {
-                    self.call_fn_at_addr(nnn);
+                    if let Err(error) = self.call_fn_at_addr(nnn) {
+                        self.status = ExecStatus::Error(error);
+                    }
                     // skip incrementing program counter.
                     return
                 },
                 (0x3, _, _, _) => {
                     // Skip the next instruction if register VX is equal to NN
                     if self.register[x] == nn {
-                        self.pc += 2;
+                        self.pc = self.pc.wrapping_add(2);
                     }
                 }
                 (0x4, _, _, _) => {
                     // Skip the next instruction if register VX is not equal to NN.
                     if self.register[x] != nn {
-                        self.pc += 2;
+                        self.pc = self.pc.wrapping_add(2);
                     }
                 }
-                (0x5, _, _, 0x0) => {
+                (0x5, _, _, n) if n == 0x0 || !self.strict => {
                     // Skip the next instruction if register VX equals VY.
                     if self.register[x] == self.register[y] {
-                        self.pc += 2;
+                        self.pc = self.pc.wrapping_add(2);
                     }
                 }
                 (0x6, _, _, _) => {
@@ -168,7 +874,7 @@ impl Chip8 {
                 }
                 (0x7, _, _, _) => {
                     // Add immediate value NN to register VX. Does not effect VF.
-                    self.register[x] = ((self.register[x] as u16 + nn as u16) & 0xff) as u8;
+                    self.register[x] = self.register[x].wrapping_add(nn);
                 }
                 (0x8, _, _, _) => {
                     match n {
@@ -204,8 +910,10 @@ impl Chip8 {
                         }
                         0x6 => {
                             // Set VX equal to VX bitshifted right 1. VF is set to the least significant bit of VX prior to the shift.
-                            self.register[0xF] = self.register[x] & 0x1;
-                            self.register[x] = self.register[x] >> 1
+                            // (COSMAC VIP: VY is shifted instead.)
+                            let source = if self.quirks.shift_uses_vy { y } else { x };
+                            self.register[0xF] = self.register[source] & 0x1;
+                            self.register[x] = self.register[source] >> 1
                         }
                         0x7 => {
                             // Set VX equal to VY minus VX. VF is set to 1 if VY > VX. Otherwise 0.
@@ -213,27 +921,30 @@ impl Chip8 {
                             let vy = self.register[y];
                             if vx > vy {
                                 // + 1 due to 0 not being counted.
-                                self.register[x] = (vy as i16 - vx as i16 + 1).abs() as u8;
+                                self.register[x] = (vy as i16 - vx as i16 + 1).unsigned_abs() as u8;
                                 self.register[0xF] = 0;
                             } else {
-                                self.register[x] = (vy as i16 - vx as i16).abs() as u8;
+                                self.register[x] = (vy as i16 - vx as i16).unsigned_abs() as u8;
                                 self.register[0xF] = 1;
                             }
                         }
                         0xE => {
                             // Set VX equal to VX bitshifted left 1. VF is set to the most significant bit of VX prior to the shift.
-                            self.register[0xF] = self.register[x] >> 7;
-                            self.register[x] = self.register[x] << 1;
+                            // (COSMAC VIP: VY is shifted instead.)
+                            let source = if self.quirks.shift_uses_vy { y } else { x };
+                            self.register[0xF] = self.register[source] >> 7;
+                            self.register[x] = self.register[source] << 1;
                         }
                         _ => {
-                            panic!("Unknown OpCode was provided. OpCode: {}", opcode);
+                            self.status = ExecStatus::Error(Chip8Error::UnknownOpcode(opcode));
+                            return
                         }
                     }
                 }
-                (0x9, _, _, 0x0) => {
+                (0x9, _, _, n) if n == 0x0 || !self.strict => {
                     // Skip the next instruction if VX does not equal VY.
                     if self.register[x] != self.register[y] {
-                        self.pc += 2;
+                        self.pc = self.pc.wrapping_add(2);
                     }
                 }
                 (0xA, _, _, _) => {
@@ -242,12 +953,14 @@ impl Chip8 {
                 }
                 (0xB, _, _, _) => {
                     // Set the PC to NNN plus the value in V0.
-                    self.pc = nnn + self.register[0] as u16;
+                    // (SUPER-CHIP: XNN plus the value in VX.)
+                    let offset_register = if self.quirks.jump_uses_vx { x } else { 0 };
+                    self.pc = nnn + self.register[offset_register] as u16;
                     return
                 }
                 (0xC, _, _, _) => {
                     // Set VX equal to a random number ranging from 0 to 255 which is logically anded with NN.
-                    let r: u8 = random();
+                    let r = self.random_byte();
                     self.register[x] = r & nn
                 }
                 (0xD, _, _, _) => {
@@ -255,41 +968,89 @@ impl Chip8 {
                     // Each set bit of xored with what's already drawn.
                     // VF is set to 1 if a collision occurs.
                     // 0 otherwise.
+                    // With the `collision_counts_rows` quirk VF holds the number of rows that collided.
+                    // VF is only written once the whole sprite is drawn, so a collision in any row counts
+                    // and VF can be used as VX or VY.
+                    // The starting position always wraps, the `clip_sprites` quirk decides whether the
+                    // pixels running past the right and bottom edges are cut off or wrap around.
 
                     let height = n;
+                    if !self.check_memory_range(self.i, height as usize) {
+                        return
+                    }
 
-                    // Set collision detection to 0
-                    self.register[0xF] = 0x0;
+                    #[cfg(debug_assertions)]
+                    if self.i < LOWER_MEMORY_BOUNDARY {
+                        if let Some(warning) = self.low_sprite_warning.as_mut() {
+                            warning(self.i);
+                        }
+                    }
 
-                    let x_coord = self.register[x];
-                    let y_coord = self.register[y];
+                    let (screen_width, screen_height) = self.screen.dimensions();
+                    let x_coord = (self.register[x] as usize % screen_width) as u8;
+                    let y_coord = (self.register[y] as usize % screen_height) as u8;
 
+                    let mut collided_rows = 0;
                     for yline in 0..height {
+                        let mut row = y_coord as usize + yline as usize;
+                        if row >= screen_height {
+                            if self.quirks.clip_sprites {
+                                break;
+                            }
+                            row %= screen_height;
+                        }
                         let pixel = self.memory[self.i + (yline as u16)];
-                        let collision = self.screen.draw_sprite_at_location(pixel, x_coord, y_coord + yline);
-                        match collision {
-                            true => self.register[0xF] = 0x1,
-                            false => ()
+                        let collision = if self.quirks.clip_sprites {
+                            self.screen.draw_sprite_at_location(pixel, x_coord, row as u8)
+                        } else {
+                            self.screen.draw_sprite_at_location_wrapped(pixel, x_coord, row as u8)
+                        };
+                        if collision {
+                            collided_rows += 1;
                         }
                     }
+
+                    self.register[0xF] = if self.quirks.collision_counts_rows {
+                        collided_rows
+                    } else {
+                        (collided_rows > 0) as u8
+                    };
+
+                    if let Some(before) = screen_before {
+                        self.report_flipped_pixels(before);
+                    }
+
+                    self.drew_in_frame = true;
+                    if self.quirks.display_wait {
+                        self.waiting_for_vblank = true;
+                    }
                 }
                 (0xE, _, 0x9, 0xE) => {
                     // Skips the next instruction if the key stored in VX is pressed
                     // (usually the next instruction is a jump to skip a code block).
-                    let key_pressed = self.keyboard.take_keypress();
-                    let key_at_x_pressed = key_pressed == Some(x as u8);
-                    match key_at_x_pressed {
-                        true => self.pc += 2,
-                        false => ()
+                    let key = self.register[x];
+                    let key_at_x_pressed = self.is_key_pressed(key);
+                    if key_at_x_pressed {
+                        self.pc = self.pc.wrapping_add(2);
                     }
                 }
                 (0xE, _, 0xA, 0x1) => {
                     // Skip the following instruction if the key represented by the value in VX is not pressed.
-                    let key_at_x_pressed = self.keyboard.was_key_pressed(x as u8);
-                    match !key_at_x_pressed {
-                        true => self.pc += 2,
-                        false => ()
+                    let key = self.register[x];
+                    let key_at_x_pressed = self.is_key_pressed(key);
+                    if !key_at_x_pressed {
+                        self.pc = self.pc.wrapping_add(2);
+                    }
+                }
+                (0xF, 0x0, 0x0, 0x0) => {
+                    // XO-CHIP: load I with the 16-bit address stored in the next word.
+                    // This is the only instruction taking up 4 bytes.
+                    let address_word = self.pc.wrapping_add(2);
+                    if !self.check_memory_range(address_word, 2) {
+                        return
                     }
+                    self.i = u16::from_be_bytes([self.memory[address_word], self.memory[address_word + 1]]);
+                    self.pc = address_word;
                 }
                 (0xF, _, 0x0, 0x7) => {
                     // Set VX equal to the delay timer.
@@ -297,9 +1058,19 @@ impl Chip8 {
                 }
                 (0xF, _, 0x0, 0xA) => {
                     // Wait for a key press and store the value of the key into VX.
-                    match self.keyboard.take_keypress() {
+                    // within a frame the key comes from the snapshot (the lowest one held), so a key
+                    // pressed during the frame is only picked up by the next one.
+                    let key = match self.key_snapshot {
+                        Some(0) => None,
+                        Some(keys) => Some(keys.trailing_zeros() as u8),
+                        None => self.input_source().wait_key(),
+                    };
+                    match key {
                         Some(key) => self.register[x] = key,
-                        None => return
+                        None => {
+                            self.status = ExecStatus::WaitingForKey;
+                            return
+                        }
                     }
                 }
                 (0xF, _, 0x1, 0x5) => {
@@ -308,25 +1079,47 @@ impl Chip8 {
                 }
                 (0xF, _, 0x1, 0x8) => {
                     // Set the sound timer ST to VX.
-                    self.sound_timer = self.register[x]
+                    if self.sound_timer == 0 && self.register[x] > 0 {
+                        self.beep_count += 1;
+                    }
+                    self.sound_timer = self.register[x];
+                    self.beeped_in_frame |= self.sound_timer > 0;
                 }
                 (0xF, _, 0x1, 0xE) => {
                     // Add VX to I. VF is set to 1 if I > 0x0FFF. Otherwise set to 0.
-                    if self.i + self.register[x] as u16 > 0xFFF {
+                    let sum = self.i.wrapping_add(self.register[x] as u16);
+                    if sum > 0xFFF {
                         self.register[0xF] = 1
                     }
-                    self.i += self.register[x] as u16;
+                    self.i = if self.quirks.wrap_index_register {
+                        sum & 0x0FFF
+                    } else {
+                        sum
+                    };
                 }
                 (0xF, _, 0x2, 0x9) => {
                     // Set I to the address of the CHIP-8 8x5 font sprite representing the value in VX.
-                    // TODO: copied
-                    self.i = (self.register[x] * 0x5) as u16;
+                    // Only the low nibble of VX is used, so garbage in the upper bits still selects a digit.
+                    let digit = (self.register[x] & 0x0F) as u16;
+                    self.i = FONTSET_START + digit * 0x5;
+                }
+                (0xF, _, 0x3, 0x0) => {
+                    // SUPER-CHIP: Set I to the address of the 8x10 font sprite representing the digit in VX.
+                    // Like FX29 only the low nibble counts, but there are only glyphs for 0-9.
+                    let digit = self.register[x] & 0x0F;
+                    if digit > 9 {
+                        self.status = ExecStatus::Error(Chip8Error::NoBigFontGlyph(digit));
+                        return
+                    }
+                    self.i = BIG_FONTSET_START + digit as u16 * 10;
                 }
                 (0xF, _, 0x3, 0x3) => {
                     // Stores the binary-coded decimal representation of VX, with the most
                     // significant of three digits at the address in I,
                     // the middle digit at I plus 1, and the least significant digit at I plus 2.
-
+                    if !self.check_memory_range(self.i, 3) {
+                        return
+                    }
                     self.memory[self.i] = self.register[x] / 100;
                     self.memory[self.i + 1] = self.register[x] / 10 % 10;
                     self.memory[self.i + 2] = self.register[x] % 10;
@@ -334,69 +1127,621 @@ impl Chip8 {
                 (0xF, _, 0x5, 0x5) => {
                     // Store registers V0 through VX in memory starting at location I.
                     // I does not change.
+                    if !self.check_memory_range(self.i, x + 1) {
+                        return
+                    }
                     for idx in 0..=x as u16 {
                         self.memory[self.i + idx] = self.register[idx as usize];
                     }
+                    if self.quirks.load_store_increments_i {
+                        self.i = self.i.wrapping_add(x as u16 + 1);
+                    }
                 }
                 (0xF, _, 0x6, 0x5) => {
                     // Copy values from memory location I through I + X into registers V0
                     // through VX. I does not change.
-
+                    if !self.check_memory_range(self.i, x + 1) {
+                        return
+                    }
                     for (starting_idx, idx) in (self.i..=self.i+x as u16).enumerate() {
                         // starting_idx starts at 0 for V0 and ends at x thanks to enumerate.
                         self.register[starting_idx] = self.memory[idx];
                     }
+                    if self.quirks.load_store_increments_i {
+                        self.i = self.i.wrapping_add(x as u16 + 1);
+                    }
+                }
+                _ => {
+                    self.status = ExecStatus::Error(Chip8Error::UnknownOpcode(opcode));
+                    return
                 }
-                _ => panic!("Unknown opcode was provided {opcode}!")
             }
 
-            self.pc += 2; // move to next instruction
-
+            self.pc = self.pc.wrapping_add(2); // move to next instruction
+    }
 
-            // Timers are updated every iteration at 60Hz.
+    // Decrements the delay and sound timers by one, i.e. one 60Hz frame went by.
+    pub fn tick_timers(&mut self) {
+        self.tick_timers_by(1);
+    }
 
-            // Update timers
-            if self.delay_timer > 0 {
-                self.delay_timer-=1;
-            }
+    // Decrements the timers by `frames` at once, e.g. for a front-end catching up after falling behind.
+    pub fn tick_timers_by(&mut self, frames: u8) {
+            self.delay_timer = self.delay_timer.saturating_sub(frames);
 
             if self.sound_timer > 0 {
-                if self.sound_timer == 1 {
-                    println!("BEEP!\n");
-
+                if self.sound_timer <= frames {
+                    log::info!("BEEP!");
                 }
-                self.sound_timer-= 1;
+                self.sound_timer = self.sound_timer.saturating_sub(frames);
             }
     }
 
-    fn execute_single_instruction(&mut self) {
+    // Executes a single instruction and reports whether the machine made progress,
+    // e.g. `WaitingForKey` tells a front-end to keep pumping input events.
+    pub fn step(&mut self) -> ExecStatus {
+        if self.paused {
+            return ExecStatus::Paused;
+        }
+
+        if self.history_capacity > 0 {
+            if self.history.len() == self.history_capacity {
+                self.history.pop_front();
+            }
+            let state = self.snapshot();
+            self.history.push_back(state);
+        }
+
+        let watched_before = self.register;
+        self.watch_hit = None;
+
         let opcode = self.read_opcode();
-        if opcode != 0x0 {
-            self.execute_instruction(opcode)
+        if self.is_halt_opcode(opcode) {
+            self.status = ExecStatus::Halted;
         } else {
-            return;
+            self.execute_instruction(opcode)
+        }
+
+        self.watch_hit = self
+            .watched_registers
+            .iter()
+            .copied()
+            .filter(|&index| watched_before.get(index) != self.register.get(index))
+            .min();
+
+        self.status()
+    }
+
+    // Like `step`, but returns the instruction that was executed, e.g. for a UI showing what ran.
+    // Returns None without executing anything if the machine is halted or the word is not an instruction.
+    pub fn step_decoded(&mut self) -> Option<Instruction> {
+        let opcode = self.read_opcode();
+        if self.paused || self.is_halt_opcode(opcode) {
+            return None;
         }
+
+        let instruction = decode(opcode)?;
+        self.step();
+        Some(instruction)
+    }
+
+    // Steps up to `cycles` times and returns the (pc, opcode) of every instruction executed,
+    // e.g. to find the first point two runs with different quirks part ways.
+    // Stops early if the program halts or fails, a failing instruction is the last entry.
+    pub fn trace_run(&mut self, cycles: usize) -> Vec<(u16, u16)> {
+        let mut trace = Vec::new();
+        for _ in 0..cycles {
+            let (pc, opcode) = (self.pc, self.read_opcode());
+            let cycles_before = self.cycle_count;
+
+            let status = self.step();
+            if self.cycle_count != cycles_before {
+                trace.push((pc, opcode));
+            }
+            if matches!(status, ExecStatus::Halted | ExecStatus::Error(_) | ExecStatus::Paused) {
+                break;
+            }
+        }
+        trace
     }
 
     pub fn run(&mut self) {
+        if self.paused {
+            return;
+        }
+
         loop {
+            // the flag is cleared once seen, so the machine can be run again afterwards.
+            if self.halt.swap(false, Ordering::SeqCst) {
+                return;
+            }
+
             let opcode = self.read_opcode();
-            if opcode != 0x0 {
-                self.execute_instruction(opcode)
-            } else {
+            if self.is_halt_opcode(opcode) {
+                self.status = ExecStatus::Halted;
+                return;
+            }
+
+            self.execute_instruction(opcode);
+            if let ExecStatus::Error(_) = self.status {
                 return;
             }
         }
     }
 
-    fn set_pc_to_addr(&mut self, addr: u16) {
-        self.pc = addr
+    // Executes up to `cycles_per_frame` instructions, i.e. one 60Hz frame worth of work.
+    // Stops early if the program halts, or after a sprite is drawn when the display wait quirk is on.
+    // The timers tick exactly once per frame, however many instructions ran.
+    pub fn run_frame(&mut self, cycles_per_frame: usize) {
+        if self.paused {
+            return;
+        }
+
+        self.waiting_for_vblank = false;
+        self.drew_in_frame = false;
+        self.beeped_in_frame = self.sound_timer > 0;
+        self.in_frame = true;
+        self.key_snapshot = Some(self.keys_snapshot());
+        self.run_frame_cycles(cycles_per_frame);
+        self.key_snapshot = None;
+        self.in_frame = false;
+        self.tick_timers();
     }
 
-    fn add(&mut self, x: usize, y: usize) {
-        let arg1 = self.register[x];
-        let arg2 = self.register[y];
-        let (val, overflow) = arg1.overflowing_add(arg2);
+    fn run_frame_cycles(&mut self, cycles_per_frame: usize) {
+        for _ in 0..cycles_per_frame {
+            if self.is_halt_opcode(self.read_opcode()) {
+                self.status = ExecStatus::Halted;
+                return;
+            }
+
+            self.step();
+
+            if self.waiting_for_vblank || matches!(self.status, ExecStatus::Error(_)) {
+                return;
+            }
+        }
+    }
+
+    // Runs frame after frame, handing the machine to `f` after each one, e.g. for a headless game loop.
+    // Stops once `f` returns false or the program halts or fails.
+    pub fn run_with_callback<F: FnMut(&Chip8) -> bool>(&mut self, cycles_per_frame: usize, mut f: F) {
+        loop {
+            self.run_frame(cycles_per_frame);
+
+            if !f(self) || matches!(self.status, ExecStatus::Halted | ExecStatus::Error(_)) {
+                return;
+            }
+        }
+    }
+
+    pub fn clock_hz(&self) -> u32 {
+        self.clock_hz
+    }
+
+    // Changes the emulation speed on the fly, e.g. for a turbo key. Takes effect with the next
+    // `run_clocked_frame`, `run_realtime` frame or `advance`.
+    pub fn set_clock_hz(&mut self, hz: u32) {
+        self.clock_hz = hz;
+    }
+
+    // One 60Hz frame worth of instructions at the current clock speed, at least one.
+    pub fn cycles_per_frame(&self) -> usize {
+        (self.clock_hz / 60).max(1) as usize
+    }
+
+    // `run_frame` with the cycle budget taken from the current clock speed.
+    pub fn run_clocked_frame(&mut self) {
+        self.run_frame(self.cycles_per_frame());
+    }
+
+    // Strict machines (the default) stop at 5XYN and 9XYN with a low nibble other than 0 as unknown
+    // opcodes, lenient ones ignore the nibble and execute them as 5XY0 and 9XY0 like some interpreters do.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    // Runs frame after frame at the speed of the real machine: `clock_hz / 60` instructions,
+    // then sleeping for whatever is left of the 1/60s. Returns like `run` does.
+    #[cfg(feature = "std")]
+    pub fn run_realtime(&mut self) {
+        let frame = Duration::from_secs(1) / 60;
+
+        loop {
+            if self.paused || self.halt.swap(false, Ordering::SeqCst) {
+                return;
+            }
+
+            let frame_start = Instant::now();
+            self.run_clocked_frame();
+            if matches!(self.status, ExecStatus::Halted | ExecStatus::Error(_)) {
+                return;
+            }
+
+            if let Some(remaining) = frame.checked_sub(frame_start.elapsed()) {
+                std::thread::sleep(remaining);
+            }
+        }
+    }
+
+    // Catches up with `elapsed` wall time: the timers tick once for every 1/60s, but at most a quarter
+    // second worth of instructions is executed, e.g. after the host window was minimized for a while.
+    pub fn advance(&mut self, elapsed: Duration) {
+        const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+        if self.paused {
+            return;
+        }
+
+        let clock_hz = self.clock_hz.max(1) as u128;
+        let cycle_time = (self.cycle_carry + elapsed).as_nanos();
+        let owed_cycles = cycle_time * clock_hz / NANOS_PER_SEC;
+        // at least one, otherwise a clock below `MAX_CATCH_UP_FRACTION` Hz would never execute anything
+        let max_cycles = (clock_hz / MAX_CATCH_UP_FRACTION as u128).max(1);
+        let cycles = if owed_cycles > max_cycles {
+            // whatever can't be caught up is dropped.
+            self.cycle_carry = Duration::ZERO;
+            max_cycles
+        } else {
+            self.cycle_carry = Duration::from_nanos((cycle_time * clock_hz % NANOS_PER_SEC / clock_hz) as u64);
+            owed_cycles
+        };
+
+        self.in_frame = true;
+        for _ in 0..cycles {
+            if matches!(self.step(), ExecStatus::Halted | ExecStatus::Error(_)) {
+                break;
+            }
+        }
+        self.in_frame = false;
+
+        let frame = (Duration::from_secs(1) / 60).as_nanos();
+        let timer_time = (self.timer_carry + elapsed).as_nanos();
+        self.timer_carry = Duration::from_nanos((timer_time % frame) as u64);
+        self.tick_timers_by((timer_time / frame).min(u8::MAX as u128) as u8);
+    }
+
+    // Whether the last `run_frame` executed a DXYN. With the display wait quirk that draw also ended
+    // the frame, so a front-end can present the screen and wait for the next vblank.
+    pub fn drew_in_frame(&self) -> bool {
+        self.drew_in_frame
+    }
+
+    // Whether the sound timer was running at any point of the last `run_frame`, even if it ran out
+    // or was stopped before the frame ended, e.g. for a "sound playing" indicator.
+    pub fn beeped_this_frame(&self) -> bool {
+        self.beeped_in_frame
+    }
+
+    // Runs `frames` frames, or fewer if the program halts or fails, and returns the resulting display.
+    // Meant for test ROMs that report their result on screen.
+    pub fn run_frames(&mut self, frames: usize, cycles_per_frame: usize) -> &Screen {
+        for _ in 0..frames {
+            self.run_frame(cycles_per_frame);
+
+            if matches!(self.status, ExecStatus::Halted | ExecStatus::Error(_)) {
+                break;
+            }
+        }
+        &self.screen
+    }
+
+    pub fn request_halt(&self) {
+        self.halt.store(true, Ordering::SeqCst);
+    }
+
+    // Whether 00E0 or DXYN changed the screen since the last call.
+    pub fn take_redraw(&mut self) -> bool {
+        self.screen.take_redraw()
+    }
+
+    // Why the last executed instruction did or did not make progress.
+    pub fn status(&self) -> ExecStatus {
+        if self.paused {
+            return ExecStatus::Paused;
+        }
+        self.status.clone()
+    }
+
+    // Suspends execution, e.g. for a pause button: stepping and running do nothing and
+    // timers don't tick until `resume` is called. The machine state is kept as is.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // A handle that can stop `run` from another thread, e.g. when a front-end shuts down.
+    pub fn halt_handle(&self) -> HaltHandle {
+        HaltHandle { halt: Arc::clone(&self.halt) }
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycle_count
+    }
+
+    // A compact dump of the CPU state for logs and failing tests, without the memory and screen.
+    pub fn debug_string(&self) -> String {
+        use core::fmt::Write;
+
+        let mut out = String::new();
+        // writing to a String can't fail
+        let _ = writeln!(out, "PC: {:#06X}  I: {:#06X}  SP: {}", self.pc, self.i, self.sp);
+        for (offset, registers) in self.register.chunks(8).enumerate() {
+            for (index, value) in registers.iter().enumerate() {
+                let _ = write!(out, "V{:X}: {:02X} ", offset * 8 + index, value);
+            }
+            out.pop();
+            out.push('\n');
+        }
+        let _ = writeln!(out, "DT: {:02X}  ST: {:02X}", self.delay_timer, self.sound_timer);
+        let _ = match self.sp.checked_sub(1).and_then(|top| self.stack.get(top)) {
+            Some(addr) => write!(out, "Stack top: {addr:#06X}"),
+            None => write!(out, "Stack top: empty"),
+        };
+        out
+    }
+
+    // How many sounds were started, i.e. how often the sound timer went from 0 to a non-zero value.
+    // Restarting a sound that is still playing doesn't count.
+    pub fn beep_count(&self) -> u64 {
+        self.beep_count
+    }
+
+    // Puts the machine back into its power-on state. The loaded program stays in memory.
+    pub fn reset(&mut self) {
+        self.pc = LOWER_MEMORY_BOUNDARY;
+        self.i = 0;
+        self.register = [0; REGISTER];
+        self.stack = [0; STACK];
+        self.sp = 0;
+        self.delay_timer = 0;
+        self.sound_timer = 0;
+        self.beep_count = 0;
+        self.cycle_count = 0;
+        self.cycle_carry = Duration::ZERO;
+        self.timer_carry = Duration::ZERO;
+        self.screen = Screen::default();
+        self.keyboard = Keypad::default();
+        self.history.clear();
+        self.watch_hit = None;
+        self.status = ExecStatus::Running;
+        self.paused = false;
+        self.waiting_for_vblank = false;
+        self.in_frame = false;
+        self.key_snapshot = None;
+        self.drew_in_frame = false;
+        self.beeped_in_frame = false;
+    }
+
+    // Keep the state before each of the last `capacity` steps so they can be undone with `step_back`.
+    // A capacity of 0 disables the history.
+    pub fn enable_history(&mut self, capacity: usize) {
+        self.history_capacity = capacity;
+        while self.history.len() > capacity {
+            self.history.pop_front();
+        }
+    }
+
+    // Restores the state from before the most recent step. Returns false if there is nothing to undo.
+    pub fn step_back(&mut self) -> bool {
+        match self.history.pop_back() {
+            Some(state) => {
+                self.restore(state);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Writes the machine state (registers, timers, stack, memory and screen) in a compact binary format,
+    // starting with a magic header and a version byte.
+    #[cfg(feature = "std")]
+    pub fn save_to_writer<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(SAVE_STATE_MAGIC)?;
+        w.write_all(&[SAVE_STATE_VERSION])?;
+        w.write_all(&self.pc.to_be_bytes())?;
+        w.write_all(&self.i.to_be_bytes())?;
+        w.write_all(&self.register)?;
+        for addr in self.stack {
+            w.write_all(&addr.to_be_bytes())?;
+        }
+        w.write_all(&[self.sp as u8, self.delay_timer, self.sound_timer])?;
+        w.write_all(&self.cycle_count.to_be_bytes())?;
+        self.memory.save_to_writer(w)?;
+        self.screen.save_to_writer(w)
+    }
+
+    // Writes the display as a black and white PNG, every pixel blown up to a `scale` x `scale` square.
+    #[cfg(feature = "image")]
+    pub fn save_screen_png<P: AsRef<Path>>(&self, path: P, scale: u32) -> Result<(), Box<dyn Error>> {
+        let (width, height) = self.screen.dimensions();
+        let scale = scale.max(1);
+        let image = image::GrayImage::from_fn(width as u32 * scale, height as u32 * scale, |x, y| {
+            let lit = self.screen.get_pixel_color((x / scale) as usize, (y / scale) as usize) != 0;
+            image::Luma([if lit { 0xFF } else { 0x00 }])
+        });
+        image.save_with_format(path, image::ImageFormat::Png)?;
+        Ok(())
+    }
+
+    // Restores a state written by `save_to_writer`. The machine is left untouched if reading fails.
+    #[cfg(feature = "std")]
+    pub fn load_from_reader<R: Read>(&mut self, r: &mut R) -> io::Result<()> {
+        let mut header = [0; 5];
+        r.read_exact(&mut header)?;
+        if &header[..4] != SAVE_STATE_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a save state"));
+        }
+        if header[4] != SAVE_STATE_VERSION {
+            let message = alloc::format!("unsupported save state version {}", header[4]);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+
+        let pc = read_u16(r)?;
+        let i = read_u16(r)?;
+        let mut register = [0; REGISTER];
+        r.read_exact(&mut register)?;
+        let mut stack = [0; STACK];
+        for addr in stack.iter_mut() {
+            *addr = read_u16(r)?;
+        }
+        let mut counters = [0; 3];
+        r.read_exact(&mut counters)?;
+        let [sp, delay_timer, sound_timer] = counters;
+        if sp as usize > STACK {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "stack pointer out of range"));
+        }
+        let mut cycle_count = [0; 8];
+        r.read_exact(&mut cycle_count)?;
+
+        let mut memory = Memory::with_size(self.memory.size());
+        memory.load_from_reader(r)?;
+        let mut screen = Screen::default();
+        screen.load_from_reader(r)?;
+
+        self.restore(Chip8State {
+            memory,
+            pc,
+            i,
+            register,
+            stack,
+            sp: sp as usize,
+            delay_timer,
+            sound_timer,
+            cycle_count: u64::from_be_bytes(cycle_count),
+            // neither is part of a save state
+            beep_count: self.beep_count,
+            status: self.status.clone(),
+            screen,
+        });
+        Ok(())
+    }
+
+    // Start counting how many times each instruction form (e.g. `ADD Vx, Vy`) gets executed.
+    pub fn enable_opcode_histogram(&mut self) {
+        self.opcode_histogram.get_or_insert_with(BTreeMap::new);
+    }
+
+    pub fn opcode_histogram(&self) -> BTreeMap<String, u64> {
+        self.opcode_histogram
+            .iter()
+            .flatten()
+            .map(|(kind, count)| (kind.to_string(), *count))
+            .collect()
+    }
+
+    // The `n` most executed instruction forms, most frequent first and ties in alphabetical order.
+    pub fn opcode_top(&self, n: usize) -> Vec<(String, u64)> {
+        let mut top: Vec<(String, u64)> = self.opcode_histogram().into_iter().collect();
+        top.sort_by_key(|&(_, count)| core::cmp::Reverse(count));
+        top.truncate(n);
+        top
+    }
+
+    pub fn add_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.insert(addr);
+    }
+
+    pub fn remove_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.remove(&addr);
+    }
+
+    // Pause `run_until_breakpoint` whenever an instruction changes the value of register V`index`.
+    pub fn watch_register(&mut self, index: usize) {
+        self.watched_registers.insert(index);
+    }
+
+    pub fn unwatch_register(&mut self, index: usize) {
+        self.watched_registers.remove(&index);
+    }
+
+    // The watched register that was changed by the most recent step, if any.
+    pub fn watch_hit(&self) -> Option<usize> {
+        self.watch_hit
+    }
+
+    // Steps until the program counter reaches a breakpoint, a watched register changes
+    // or execution stops (halted, an error, or waiting for a key), and returns the address
+    // execution stopped at.
+    pub fn run_until_breakpoint(&mut self) -> u16 {
+        if self.paused {
+            return self.pc;
+        }
+
+        loop {
+            if self.is_halt_opcode(self.read_opcode()) {
+                self.status = ExecStatus::Halted;
+                return self.pc;
+            }
+
+            let status = self.step();
+
+            if status != ExecStatus::Running || self.breakpoints.contains(&self.pc) || self.watch_hit.is_some() {
+                return self.pc;
+            }
+        }
+    }
+
+    fn snapshot(&self) -> Chip8State {
+        Chip8State {
+            memory: self.memory.clone(),
+            pc: self.pc,
+            i: self.i,
+            register: self.register,
+            stack: self.stack,
+            sp: self.sp,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            cycle_count: self.cycle_count,
+            beep_count: self.beep_count,
+            status: self.status.clone(),
+            screen: self.screen.clone(),
+        }
+    }
+
+    fn restore(&mut self, state: Chip8State) {
+        self.memory = state.memory;
+        self.pc = state.pc;
+        self.i = state.i;
+        self.register = state.register;
+        self.stack = state.stack;
+        self.sp = state.sp;
+        self.delay_timer = state.delay_timer;
+        self.sound_timer = state.sound_timer;
+        self.cycle_count = state.cycle_count;
+        self.beep_count = state.beep_count;
+        self.status = state.status;
+        self.screen = state.screen;
+    }
+
+    #[cfg(feature = "std")]
+    fn random_byte(&mut self) -> u8 {
+        rand::random()
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn random_byte(&mut self) -> u8 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 17;
+        self.rng_state ^= self.rng_state << 5;
+        (self.rng_state >> 24) as u8
+    }
+
+    fn set_pc_to_addr(&mut self, addr: u16) {
+        self.pc = addr
+    }
+
+    fn add(&mut self, x: usize, y: usize) {
+        let arg1 = self.register[x];
+        let arg2 = self.register[y];
+        let (val, overflow) = arg1.overflowing_add(arg2);
         self.register[x] = val;
         // CHIP-8 uses the last register as carry flag, indicating that an operation has overflowed.
         if overflow {
@@ -412,7 +1757,7 @@ impl Chip8 {
 
         if vy > vx {
             // borrow occurs
-            let val = (vx as i16 - vy as i16 + 1).abs() as u8;
+            let val = (vx as i16 - vy as i16 + 1).unsigned_abs() as u8;
             self.register[x] = val;
             self.register[0xF] = 0;
         } else {
@@ -420,25 +1765,11 @@ impl Chip8 {
             self.register[0xF] = 1;
         }
     }
-
-    fn subn(&mut self, x: usize, y: usize) {
-        let arg1 = self.register[x];
-        let arg2 = self.register[y];
-        let (val, underflow) = arg1.overflowing_sub(arg2);
-        self.register[x] = val;
-        // CHIP-8 uses the last register as carry flag, indicating that an operation has overflowed.
-        if underflow {
-            self.register[0xF] = 0;
-        } else {
-            self.register[0xF] = 1;
-        }
-    }
 }
 
 
 #[cfg(test)]
 mod tests {
-    use std::error::Error;
     use super::*;
 
     #[test]
@@ -449,859 +1780,3051 @@ mod tests {
         assert!(chip8.is_ok())
     }
 
+    #[test]
+    fn test_load_program_from_slice() {
+        let program: &[u8] = &[0x60, 0x05, 0x70, 0x01];
+        let mut chip8 = Chip8::new();
+
+        chip8.load_into_memory(program).unwrap();
+        chip8.run();
+
+        assert_eq!(chip8.register[0], 6);
+    }
+
     #[test]
     fn test_load_program_that_is_too_big() {
-        let program: Vec<u8> = [0; 8192].to_vec();
+        let program: Vec<u8> = [0; 65536].to_vec();
 
         let chip8 = create_and_load(&program);
         assert!(chip8.is_err())
     }
 
     #[test]
-    fn test_clear_screen() {
-        // 0x00E0; clear the screen
-        let program: Vec<u8> = vec![0x0, 0xE0];
+    fn test_load_program_at_custom_address() {
+        // 0x64AA at 0x600; sets V4 to 0xAA
+        let program: Vec<u8> = vec![0x64, 0xAA];
 
-        let mut chip8 = create_and_load(&program).unwrap();
+        let mut chip8 = Chip8::new();
+        chip8.load_into_memory_at(program, 0x600).unwrap();
 
-        // draw a box for testing
-        for y in 0..10 {
-            for x in 0..10 {
-                chip8.screen.draw_pixel_at_location(x, y);
-            }
-        }
+        assert_eq!(chip8.pc, 0x600);
+        assert_eq!(chip8.memory[0x600], 0x64);
+        assert_eq!(chip8.memory[0x601], 0xAA);
+        assert_eq!(chip8.memory[LOWER_MEMORY_BOUNDARY], 0x0);
 
         chip8.run();
 
-        let all_empty = chip8.screen.how_many_ones();
+        assert_eq!(chip8.register[4], 0xAA);
+    }
 
-        assert_eq!(all_empty, 0);
-        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 2);
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_load_and_run_rom() {
+        // V0 = 5, V1 = 10, V0 += V1
+        let rom: [u8; 6] = [0x60, 0x05, 0x61, 0x0A, 0x80, 0x14];
+
+        let path = std::env::temp_dir().join(format!("chip8_load_and_run_{}.ch8", std::process::id()));
+        std::fs::write(&path, rom).unwrap();
+
+        let chip8 = Chip8::load_and_run_rom(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(chip8.unwrap().get_value_at_register_addr(0), Some(15));
     }
 
+    #[cfg(feature = "std")]
     #[test]
-    fn test_return_from_subroutine() {
-        // 0x00EE; returns from subroutine
+    fn test_load_and_run_missing_rom() {
+        let chip8 = Chip8::load_and_run_rom("this/rom/does/not/exist.ch8");
+
+        assert!(chip8.is_err());
+    }
+
+    #[test]
+    fn test_cycle_count() {
         let program: Vec<u8> = vec![
-            0x22, 0xA,
-            0x0, 0x0,
-            0x0, 0xE0,
-            0x0, 0xE0,
-            0x0, 0xE0,
-            0x0, 0xEE,
+            0x60, 0x05,
+            0x61, 0x0A,
+            0x80, 0x14,
+            0x0, 0x0 // exit
         ];
 
         let mut chip8 = create_and_load(&program).unwrap();
 
-        chip8.i = LOWER_MEMORY_BOUNDARY;
+        assert_eq!(chip8.cycles(), 0);
 
-        assert_eq!(chip8.sp, 0);
+        chip8.step();
 
-        let orig_pc = chip8.pc;
+        assert_eq!(chip8.cycles(), 1);
 
-        // the first time through will jump to the beginning
-        // of the subroutine
         chip8.run();
 
-        // assert_eq!(chip8.pc, 0x20A);
-        // assert_eq!(chip8.sp, 1);
-        // assert_eq!(chip8.stack[0], LOWER_MEMORY_BOUNDARY as u16);
-        //
-        // and the second time through should return from it
-        // chip8.run();
-
-        assert_eq!(chip8.pc, orig_pc + 2);
-        assert_eq!(chip8.sp, 0);
-        assert_eq!(chip8.memory[chip8.pc], 0x0);
+        assert_eq!(chip8.cycles(), 3);
     }
 
     #[test]
-    fn test_jump_to_address() {
-        // 0x1NNN: jumps to address NNN
-        let program: Vec<u8> = vec![
-            0x10, 0xDC
-        ];
+    fn test_reset() {
+        let program: Vec<u8> = vec![0x64, 0xAA, 0xF4, 0x15];
 
         let mut chip8 = create_and_load(&program).unwrap();
 
-        assert_eq!(chip8.memory[0xDC], 0);
-        chip8.memory[0xDC] = 0x0;
-        chip8.memory[0xDD] = 0x0;
-        assert_eq!(chip8.memory[0xDC], 0x0);
-        assert_eq!(chip8.memory[0xDD], 0x0);
-
         chip8.run();
 
-        assert_eq!(chip8.pc, 0xDC);
-        assert_eq!(chip8.memory[chip8.pc], 0x0);
+        assert_eq!(chip8.register[4], 0xAA);
+        assert_ne!(chip8.delay_timer, 0);
+
+        chip8.reset();
+
+        assert_eq!(chip8.cycles(), 0);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY);
+        assert_eq!(chip8.register[4], 0);
+        assert_eq!(chip8.delay_timer, 0);
+        assert_eq!(chip8.memory[LOWER_MEMORY_BOUNDARY], 0x64);
     }
 
     #[test]
-    fn test_call_subroutine_at_nnn() {
-        // 0x2NNN: calls subroutine at NNN
-        let program: Vec<u8> = vec![0x20, 0xDC];
+    fn test_reset_after_pause() {
+        let program: Vec<u8> = vec![0x64, 0xAA];
 
         let mut chip8 = create_and_load(&program).unwrap();
+        chip8.pause();
 
-        assert_eq!(chip8.sp, 0);
+        assert_eq!(chip8.step(), ExecStatus::Paused);
 
-        chip8.run();
+        chip8.reset();
 
-        assert_eq!(chip8.pc, 0xDC);
-        assert_eq!(chip8.sp, 1);
-        assert_eq!(chip8.stack[0], 512);
+        assert!(!chip8.is_paused());
+        assert_eq!(chip8.step(), ExecStatus::Running);
+        assert_eq!(chip8.register[4], 0xAA);
     }
 
     #[test]
-    fn test_skip_next_instruction_if_vx_equals_nn_positive() {
-        // 0x3XNN: Skips the next instruction if VX equals NN.
-        let program: Vec<u8> = vec![0x34, 0x17];
+    fn test_step_back() {
+        let program: Vec<u8> = vec![
+            0x60, 0x05, // V0 = 5
+            0x61, 0x0A, // V1 = 10
+            0x80, 0x14, // V0 += V1
+        ];
 
         let mut chip8 = create_and_load(&program).unwrap();
+        chip8.enable_history(64);
 
-        chip8.register[4] = 0x17;
+        assert!(!chip8.step_back());
 
-        let orig_pc = chip8.pc;
+        chip8.step();
+        chip8.step();
+        chip8.step();
 
-        chip8.run();
+        assert_eq!(chip8.register[0], 15);
 
-        assert_eq!(chip8.pc, orig_pc + 4);
+        assert!(chip8.step_back());
+        assert!(chip8.step_back());
+
+        assert_eq!(chip8.register[0], 5);
+        assert_eq!(chip8.register[1], 0);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 2);
+        assert_eq!(chip8.cycles(), 1);
+
+        assert!(chip8.step_back());
+        assert!(!chip8.step_back());
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY);
     }
 
     #[test]
-    fn test_skip_next_instruction_if_vx_equals_nn_negative() {
-        // 0x3XNN: Skips the next instruction if VX equals NN.
+    fn test_step_back_history_capacity() {
+        let program: Vec<u8> = vec![0x70, 0x01, 0x70, 0x01, 0x70, 0x01];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.enable_history(2);
+
+        chip8.step();
+        chip8.step();
+        chip8.step();
+
+        assert!(chip8.step_back());
+        assert!(chip8.step_back());
+        assert!(!chip8.step_back());
+
+        assert_eq!(chip8.register[0], 1);
+    }
+
+    #[test]
+    fn test_step_back_restores_status_and_beep_count() {
+        let program: Vec<u8> = vec![
+            0x60, 0x05, // V0 = 5
+            0xF0, 0x18, // sound timer = V0
+            0x0, 0x0 // exit
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.enable_history(8);
+
+        chip8.step();
+        chip8.step();
+
+        assert_eq!(chip8.step(), ExecStatus::Halted);
+        assert_eq!(chip8.beep_count(), 1);
+
+        assert!(chip8.step_back());
+
+        assert_eq!(chip8.status(), ExecStatus::Running);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 4);
+        assert_eq!(chip8.beep_count(), 1);
+
+        assert!(chip8.step_back());
+
+        assert_eq!(chip8.status(), ExecStatus::Running);
+        assert_eq!(chip8.beep_count(), 0);
+    }
+
+    #[test]
+    fn test_run_until_breakpoint() {
+        let program: Vec<u8> = vec![
+            0x60, 0x05,
+            0x61, 0x0A,
+            0x80, 0x14,
+            0x0, 0x0 // exit
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.add_breakpoint(LOWER_MEMORY_BOUNDARY + 4);
+
+        let stopped_at = chip8.run_until_breakpoint();
+
+        assert_eq!(stopped_at, LOWER_MEMORY_BOUNDARY + 4);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 4);
+        assert_eq!(chip8.register[0], 5);
+        assert_eq!(chip8.register[1], 10);
+
+        // continuing runs until the program halts.
+        let stopped_at = chip8.run_until_breakpoint();
+
+        assert_eq!(stopped_at, LOWER_MEMORY_BOUNDARY + 6);
+        assert_eq!(chip8.register[0], 15);
+    }
+
+    #[test]
+    fn test_remove_breakpoint() {
+        let program: Vec<u8> = vec![0x60, 0x05, 0x61, 0x0A];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.add_breakpoint(LOWER_MEMORY_BOUNDARY + 2);
+        chip8.remove_breakpoint(LOWER_MEMORY_BOUNDARY + 2);
+
+        assert_eq!(chip8.run_until_breakpoint(), LOWER_MEMORY_BOUNDARY + 4);
+    }
+
+    #[test]
+    fn test_run_until_breakpoint_stops_waiting_for_a_key() {
+        let program: Vec<u8> = vec![
+            0x60, 0x05,
+            0xF0, 0x0A,
+            0x12, 0x04, // loop forever
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.run_until_breakpoint(), LOWER_MEMORY_BOUNDARY + 2);
+        assert_eq!(chip8.status(), ExecStatus::WaitingForKey);
+    }
+
+    #[test]
+    fn test_watch_register() {
+        let program: Vec<u8> = vec![
+            0x60, 0x05, // V0 = 5
+            0x64, 0xAA, // V4 = 0xAA
+            0x61, 0x0A, // V1 = 10
+            0x0, 0x0 // exit
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.watch_register(4);
+
+        let stopped_at = chip8.run_until_breakpoint();
+
+        // stopped right after the instruction writing V4
+        assert_eq!(stopped_at, LOWER_MEMORY_BOUNDARY + 4);
+        assert_eq!(chip8.watch_hit(), Some(4));
+        assert_eq!(chip8.register[4], 0xAA);
+        assert_eq!(chip8.register[1], 0);
+
+        chip8.run_until_breakpoint();
+
+        assert_eq!(chip8.watch_hit(), None);
+        assert_eq!(chip8.register[1], 10);
+    }
+
+    #[test]
+    fn test_watch_register_ignores_writes_of_same_value() {
+        let program: Vec<u8> = vec![0x64, 0x00, 0x0, 0x0];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.watch_register(4);
+
+        chip8.step();
+
+        assert_eq!(chip8.watch_hit(), None);
+    }
+
+    #[test]
+    fn test_peek_opcode() {
+        let program: Vec<u8> = vec![0x64, 0xAA, 0x84, 0x54];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.peek_opcode(), 0x64AA);
+        assert_eq!(chip8.peek_opcode(), 0x64AA);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY);
+
+        chip8.step();
+
+        assert_eq!(chip8.peek_opcode(), 0x8454);
+    }
+
+    #[test]
+    fn test_step_decoded() {
+        let program: Vec<u8> = vec![0x60, 0x05, 0x80, 0x14];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.step_decoded(), Some(Instruction::LdByte { x: 0, nn: 0x05 }));
+        assert_eq!(chip8.step_decoded(), Some(Instruction::Add { x: 0, y: 1 }));
+        assert_eq!(chip8.register[0], 5);
+
+        // end of the program
+        assert_eq!(chip8.step_decoded(), None);
+        assert_eq!(chip8.cycles(), 2);
+    }
+
+    #[test]
+    fn test_run_frame() {
+        let program: Vec<u8> = vec![0x70, 0x01, 0x70, 0x01, 0x70, 0x01, 0x70, 0x01, 0x70, 0x01];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.run_frame(2);
+
+        assert_eq!(chip8.cycles(), 2);
+        assert_eq!(chip8.register[0], 2);
+
+        // stops at the end of the program
+        chip8.run_frame(10);
+
+        assert_eq!(chip8.cycles(), 5);
+    }
+
+    #[test]
+    fn test_display_wait_ends_frame_after_draw() {
+        let program: Vec<u8> = vec![
+            0xD0, 0x15,
+            0xD0, 0x15,
+            0x70, 0x01,
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.set_quirks(Quirks::cosmac_vip());
+
+        chip8.run_frame(10);
+
+        assert_eq!(chip8.cycles(), 1);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 2);
+
+        chip8.run_frame(10);
+
+        assert_eq!(chip8.cycles(), 2);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 4);
+
+        chip8.run_frame(10);
+
+        assert_eq!(chip8.register[0], 1);
+    }
+
+    #[test]
+    fn test_draws_do_not_end_frame_without_display_wait() {
+        let program: Vec<u8> = vec![0xD0, 0x15, 0xD0, 0x15];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.run_frame(10);
+
+        assert_eq!(chip8.cycles(), 2);
+    }
+
+    #[test]
+    fn test_request_halt_stops_infinite_loop() {
+        // 0x1200: jumps to itself forever
+        let program: Vec<u8> = vec![0x12, 0x00];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        let halt_handle = chip8.halt_handle();
+
+        let runner = std::thread::spawn(move || {
+            chip8.run();
+            chip8
+        });
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        halt_handle.request_halt();
+
+        let chip8 = runner.join().unwrap();
+
+        assert!(chip8.cycles() > 0);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY);
+    }
+
+    #[test]
+    fn test_request_halt_is_cleared_after_stopping() {
+        let program: Vec<u8> = vec![0x64, 0xAA];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.request_halt();
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0);
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0xAA);
+    }
+
+    #[test]
+    fn test_fontset_is_loaded() {
+        let chip8 = Chip8::new();
+
+        for (idx, byte) in CHIP8_FONTSET.iter().enumerate() {
+            assert_eq!(chip8.memory[FONTSET_START + idx as u16], *byte);
+        }
+    }
+
+    #[test]
+    fn test_new_blank_has_no_fontset() {
+        let blank = Chip8::new_blank();
+        let chip8 = Chip8::new();
+
+        let font_region = FONTSET_START..BIG_FONTSET_START + SCHIP_BIG_FONTSET.len() as u16;
+        assert!(font_region.clone().all(|addr| blank.memory[addr] == 0));
+        assert!(font_region.clone().any(|addr| chip8.memory[addr] != 0));
+        assert_eq!(blank.memory.size(), chip8.memory.size());
+        assert_eq!(blank.pc, chip8.pc);
+    }
+
+    #[test]
+    fn test_custom_font() {
+        let program: Vec<u8> = vec![
+            0x61, 0x01, // V1 = 1
+            0xF1, 0x29, // I = sprite for the digit in V1
+            0xD0, 0x05, // draw it at (V0, V0)
+        ];
+
+        // every glyph is a full 8x5 block, except for the 1 which is a single column
+        let mut font = [0xFF; 80];
+        font[5..10].copy_from_slice(&[0x80; 5]);
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.set_font(&font).unwrap();
+
+        chip8.run();
+
+        assert_eq!(chip8.screen.how_many_ones(), 5);
+        for y in 0..5 {
+            assert_eq!(chip8.screen.get_pixel_color(0, y), 1);
+            assert_eq!(chip8.screen.get_pixel_color(1, y), 0);
+        }
+    }
+
+    #[test]
+    fn test_set_font_rejects_other_lengths() {
+        let mut chip8 = Chip8::new();
+
+        assert_eq!(chip8.set_font(&[0xFF; 100]), Err(Chip8Error::InvalidFontLength(100)));
+        assert_eq!(chip8.set_font(&[0xFF; 75]), Err(Chip8Error::InvalidFontLength(75)));
+        // neither font was written, the big one is untouched
+        assert_eq!(chip8.memory[FONTSET_START], 0xF0);
+        assert_eq!(chip8.memory[BIG_FONTSET_START], 0xFF);
+        assert_eq!(chip8.memory[BIG_FONTSET_START + 2], 0xC3);
+    }
+
+    #[test]
+    fn test_read_byte() {
+        let chip8 = create_and_load(&[0x64, 0xAA]).unwrap();
+
+        // first byte of the `0` glyph
+        assert_eq!(chip8.read_byte(FONTSET_START), Some(0xF0));
+        assert_eq!(chip8.read_byte(FONTSET_START + 5), Some(0x20));
+        assert_eq!(chip8.read_byte(LOWER_MEMORY_BOUNDARY + 1), Some(0xAA));
+        assert_eq!(chip8.memory().read_byte(LOWER_MEMORY_BOUNDARY), Some(0x64));
+    }
+
+    #[test]
+    fn test_opcode_histogram() {
+        // the arithmetic demo from main.rs: 5 + 10 + 10 + 10
+        let program: Vec<u8> = vec![0x80, 0x14, 0x80, 0x24, 0x80, 0x34];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.populate_register(Vec::from([5, 10, 10, 10]));
+        chip8.enable_opcode_histogram();
+
+        chip8.run();
+
+        let histogram = chip8.opcode_histogram();
+
+        assert_eq!(histogram.get("ADD Vx, Vy"), Some(&3));
+        assert_eq!(histogram.get("LD Vx, byte"), None);
+        assert_eq!(histogram.len(), 1);
+    }
+
+    #[test]
+    fn test_opcode_top() {
+        let program: Vec<u8> = vec![
+            0x60, 0x05,
+            0x61, 0x0A,
+            0x80, 0x14,
+            0x70, 0x01,
+            0x80, 0x14,
+            0x80, 0x14,
+            0xA2, 0x00,
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.enable_opcode_histogram();
+        chip8.run();
+
+        assert_eq!(
+            chip8.opcode_top(2),
+            vec![("ADD Vx, Vy".to_string(), 3), ("LD Vx, byte".to_string(), 2)]
+        );
+        assert_eq!(chip8.opcode_top(10).len(), 4);
+        assert!(chip8.opcode_top(0).is_empty());
+    }
+
+    #[test]
+    fn test_opcode_histogram_counts_forms_separately() {
+        let program: Vec<u8> = vec![0x60, 0x05, 0x61, 0x0A, 0x80, 0x14, 0x70, 0x01];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert!(chip8.opcode_histogram().is_empty());
+
+        chip8.enable_opcode_histogram();
+        chip8.run();
+
+        let histogram = chip8.opcode_histogram();
+
+        assert_eq!(histogram.get("LD Vx, byte"), Some(&2));
+        assert_eq!(histogram.get("ADD Vx, Vy"), Some(&1));
+        assert_eq!(histogram.get("ADD Vx, byte"), Some(&1));
+    }
+
+    #[test]
+    fn test_clear_screen() {
+        // 0x00E0; clear the screen
+        let program: Vec<u8> = vec![0x0, 0xE0];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        // draw a box for testing
+        for y in 0..10 {
+            for x in 0..10 {
+                chip8.screen.draw_pixel_at_location(x, y);
+            }
+        }
+
+        chip8.run();
+
+        let all_empty = chip8.screen.how_many_ones();
+
+        assert_eq!(all_empty, 0);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 2);
+    }
+
+    #[test]
+    fn test_return_from_subroutine() {
+        // 0x00EE; returns from subroutine
+        let program: Vec<u8> = vec![
+            0x22, 0xA,
+            0x0, 0x0,
+            0x0, 0xE0,
+            0x0, 0xE0,
+            0x0, 0xE0,
+            0x0, 0xEE,
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.i = LOWER_MEMORY_BOUNDARY;
+
+        assert_eq!(chip8.sp, 0);
+
+        let orig_pc = chip8.pc;
+
+        // the first time through will jump to the beginning
+        // of the subroutine
+        chip8.run();
+
+        // assert_eq!(chip8.pc, 0x20A);
+        // assert_eq!(chip8.sp, 1);
+        // assert_eq!(chip8.stack[0], LOWER_MEMORY_BOUNDARY as u16);
+        //
+        // and the second time through should return from it
+        // chip8.run();
+
+        assert_eq!(chip8.pc, orig_pc + 2);
+        assert_eq!(chip8.sp, 0);
+        assert_eq!(chip8.memory[chip8.pc], 0x0);
+    }
+
+    #[test]
+    fn test_jump_to_address() {
+        // 0x1NNN: jumps to address NNN
+        let program: Vec<u8> = vec![
+            0x10, 0xDC
+        ];
+
+        // no fonts, the target is in the big font otherwise.
+        let mut chip8 = Chip8::new_blank();
+        chip8.load_into_memory(program).unwrap();
+
+        assert_eq!(chip8.memory[0xDC], 0);
+        chip8.memory[0xDC] = 0x0;
+        chip8.memory[0xDD] = 0x0;
+        assert_eq!(chip8.memory[0xDC], 0x0);
+        assert_eq!(chip8.memory[0xDD], 0x0);
+
+        chip8.run();
+
+        assert_eq!(chip8.pc, 0xDC);
+        assert_eq!(chip8.memory[chip8.pc], 0x0);
+    }
+
+    #[test]
+    fn test_call_subroutine_at_nnn() {
+        // 0x2NNN: calls subroutine at NNN
+        let program: Vec<u8> = vec![0x20, 0xDC];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.sp, 0);
+
+        chip8.run();
+
+        assert_eq!(chip8.pc, 0xDC);
+        assert_eq!(chip8.sp, 1);
+        assert_eq!(chip8.stack[0], 512);
+    }
+
+    #[test]
+    fn test_skip_next_instruction_if_vx_equals_nn_positive() {
+        // 0x3XNN: Skips the next instruction if VX equals NN.
+        let program: Vec<u8> = vec![0x34, 0x17];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0x17;
+
+        let orig_pc = chip8.pc;
+
+        chip8.run();
+
+        assert_eq!(chip8.pc, orig_pc + 4);
+    }
+
+    #[test]
+    fn test_skip_next_instruction_if_vx_equals_nn_negative() {
+        // 0x3XNN: Skips the next instruction if VX equals NN.
         let program: Vec<u8> = vec![0x34, 0x17];
 
         let mut chip8 = create_and_load(&program).unwrap();
 
-        chip8.register[4] = 0x23;
+        chip8.register[4] = 0x23;
+
+        let orig_pc = chip8.pc;
+
+        chip8.run();
+
+        assert_eq!(chip8.pc, orig_pc + 2);
+    }
+
+    #[test]
+    fn test_skip_next_instruction_if_vx_does_not_equal_nn_positive() {
+        // 0x4XNN: Skips the next instruction if VX doesn't equal NN.
+        let program: Vec<u8> = vec![0x44, 0x17];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0x23;
+
+        let orig_pc = chip8.pc;
+
+        chip8.run();
+
+        assert_eq!(chip8.pc, orig_pc + 4);
+    }
+
+    #[test]
+    fn test_skip_next_instruction_if_vx_does_not_equal_nn_negative() {
+        // 0x4XNN: Skips the next instruction if VX doesn't equal NN.
+        let program: Vec<u8> = vec![0x44, 0x17];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0x17;
+
+        let orig_pc = chip8.pc;
+
+        chip8.run();
+
+        assert_eq!(chip8.pc, orig_pc + 2);
+    }
+
+    #[test]
+    fn test_skip_next_instruction_if_vx_equals_vy_positive() {
+        // 0x5XY0: Skips the next instruction if VX equals VY.
+        let program: Vec<u8> = vec![0x54, 0x60];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0x17;
+        chip8.register[6] = 0x17;
+
+        let orig_pc = chip8.pc;
+
+        chip8.run();
+
+        assert_eq!(chip8.pc, orig_pc + 4);
+    }
+
+    #[test]
+    fn test_skip_next_instruction_if_vx_equals_vy_negative() {
+        // 0x5XY0: Skips the next instruction if VX equals VY.
+        let program: Vec<u8> = vec![0x54, 0x60];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0x17;
+        chip8.register[6] = 0x23;
+
+        let orig_pc = chip8.pc;
+
+        chip8.run();
+
+        assert_eq!(chip8.pc, orig_pc + 2);
+    }
+
+    #[test]
+    fn test_set_vx_to_nn() {
+        // 0x6XNN: Sets VX to NN.
+        let program: Vec<u8> = vec![0x64, 0xAA];
+
+        let chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.register[4], 0);
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0xAA);
+    }
+
+    #[test]
+    fn test_add_nn_to_vx() {
+        // 0x7XNN: Adds NN to VX. (Carry flag is not changed)
+        let program: Vec<u8> = vec![0x74, 0xAA];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0x10;
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0xBA);
+    }
+
+    #[test]
+    fn test_add_nn_to_vx_wrapping() {
+        // 0x7XNN: Adds NN to VX. (Carry flag is not changed)
+        let program: Vec<u8> = vec![0x74, 0xAA, 0x0, 0x0];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0xBA;
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0x64);
+    }
+
+    #[test]
+    fn test_add_nn_to_vx_wraps_to_zero() {
+        // 0x7XNN: Adds NN to VX. (Carry flag is not changed)
+        let program: Vec<u8> = vec![0x74, 0x01];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0xFF;
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0x00);
+        assert_eq!(chip8.register[0xF], 0);
+    }
+
+    #[test]
+    fn test_set_vx_to_value_of_vy() {
+        // 0x8XY0: Sets VX to the value of VY.
+        let program: Vec<u8> = vec![0x84, 0x50];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0xBA;
+        chip8.register[5] = 0xDD;
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0xDD);
+        assert_eq!(chip8.register[5], 0xDD);
+    }
+
+    #[test]
+    fn test_set_vx_to_vx_or_vy() {
+        // 0x8XY1: Sets VX to VX or VY. (Bitwise OR operation)
+        let program: Vec<u8> = vec![0x84, 0x51];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0xBA;
+        chip8.register[5] = 0xCC;
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0xFE);
+        assert_eq!(chip8.register[5], 0xCC);
+    }
+
+    #[test]
+    fn test_set_vx_to_vx_and_vy() {
+        // 0x8XY2: Sets VX to VX and VY. (Bitwise AND operation)
+        let program: Vec<u8> = vec![0x84, 0x52];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0xBA;
+        chip8.register[5] = 0xCC;
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0x88);
+        assert_eq!(chip8.register[5], 0xCC);
+    }
+
+    #[test]
+    fn test_set_vx_to_vx_xor_vy() {
+        // 0x8XY3: Sets VX to VX xor VY.
+        let program: Vec<u8> = vec![0x84, 0x53];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0xBA;
+        chip8.register[5] = 0xCC;
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0x76);
+        assert_eq!(chip8.register[5], 0xCC);
+    }
+
+    #[test]
+    fn test_add_vy_to_vx_with_carry() {
+        // 0x8XY4: Adds VY to VX. VF is set to 1 when there's a carry, and to 0 when there isn't.
+        let program: Vec<u8> = vec![0x84, 0x54];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.register[0xF], 0);
+
+        chip8.register[4] = 0xBA;
+        chip8.register[5] = 0xCC;
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0x86);
+        assert_eq!(chip8.register[0xF], 1);
+    }
+
+    #[test]
+    fn test_add_vy_to_vx_without_carry() {
+        // 0x8XY4: Adds VY to VX. VF is set to 1 when there's a carry, and to 0 when there isn't.
+        let program: Vec<u8> = vec![0x84, 0x54];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.register[0xF], 0);
+
+        chip8.register[4] = 0xBA;
+        chip8.register[5] = 0x10;
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0xCA);
+        assert_eq!(chip8.register[0xF], 0);
+    }
+
+    #[test]
+    fn test_subtract_vy_from_vx_with_borrow() {
+        // 0x8XY5: VY is subtracted from VX. VF is set to 0 when there's a borrow,
+        // and 1 when there isn't.
+        let program: Vec<u8> = vec![0x84, 0x55];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0xBA;
+        chip8.register[5] = 0xCC;
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0x11);
+        assert_eq!(chip8.register[0xF], 0);
+    }
+
+    #[test]
+    fn test_store_least_significant_bit_of_vx_in_vf_and_shift_vx_right_by_1() {
+        // 0x8XY6: Stores the least significant bit of VX in VF and then shifts VX to
+        // the right by 1.
+        let program: Vec<u8> = vec![0x84, 0x56];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0xBB;
+        chip8.register[0xF] = 0x0;
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0x5D);
+        assert_eq!(chip8.register[0xF], 1);
+    }
+
+    #[test]
+    fn test_set_vx_to_vy_minus_vx_with_borrow() {
+        // 0x8XY7: Sets VX to VY minus VX. VF is set to 0 when there's a borrow, and 1
+        // when there isn't.
+        let program: Vec<u8> = vec![0x84, 0x57];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0xCC;
+        chip8.register[5] = 0xBA;
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0x11);
+        assert_eq!(chip8.register[0xF], 0);
+    }
+
+    #[test]
+    fn test_store_most_significant_bit_of_vx_in_vf_and_shift_vx_right_by_1() {
+        // 0x8XYE: Stores the most significant bit of VX in VF and then shifts VX to the left by 1.
+        let program: Vec<u8> = vec![0x84, 0x5E];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0xF0;
+        chip8.register[0xF] = 0x0;
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], 0xE0);
+        assert_eq!(chip8.register[0xF], 1);
+    }
+
+    #[test]
+    fn test_shift_quirk_uses_vy() {
+        // 0x8XY6 / 0x8XYE on the COSMAC VIP: VX = VY shifted, VF = shifted out bit.
+        let program: Vec<u8> = vec![0x84, 0x56, 0x86, 0x5E];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.set_quirks(Quirks::cosmac_vip());
+
+        chip8.register[4] = 0xFF;
+        chip8.register[5] = 0x81;
+        chip8.register[6] = 0x00;
+
+        chip8.step();
+
+        assert_eq!(chip8.register[4], 0x40);
+        assert_eq!(chip8.register[0xF], 1);
+
+        chip8.step();
+
+        assert_eq!(chip8.register[6], 0x02);
+        assert_eq!(chip8.register[0xF], 1);
+        assert_eq!(chip8.register[5], 0x81);
+    }
+
+    #[test]
+    fn test_skip_next_instruction_if_vx_does_not_equal_vy_positive() {
+        // 0x9XY0: Skips the next instruction if VX doesn't equal VY. (Usually the next
+        // instruction is a jump to skip a code block)
+        let program: Vec<u8> = vec![0x94, 0x60];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0x23;
+        chip8.register[6] = 0x17;
+
+        let orig_pc = chip8.pc;
+
+        chip8.run();
+
+        assert_eq!(chip8.pc, orig_pc + 4);
+    }
+
+    #[test]
+    fn test_skip_next_instruction_if_vx_does_not_equal_vy_negative() {
+        // 0x9XY0: Skips the next instruction if VX doesn't equal VY. (Usually the next
+        // instruction is a jump to skip a code block)
+        let program: Vec<u8> = vec![0x94, 0x60];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 0x17;
+        chip8.register[6] = 0x17;
+
+        let orig_pc = chip8.pc;
+
+        chip8.run();
+
+        assert_eq!(chip8.pc, orig_pc + 2);
+    }
+
+    #[test]
+    fn test_set_i_to_address_nnn() {
+        // 0xANNN: sets I to the address NNN
+        let program: Vec<u8> = vec![0xA0, 0xDC];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.i, 0);
+
+        chip8.run();
+
+        assert_eq!(chip8.i, 0xDC);
+    }
+
+    #[test]
+    fn test_jump_to_nnn_plus_v0() {
+        // 0xBNNN: Jumps to the address NNN plus V0.
+        let program: Vec<u8> = vec![0xB0, 0xDC];
+
+        // no fonts, the target is in the big font otherwise.
+        let mut chip8 = Chip8::new_blank();
+        chip8.load_into_memory(program).unwrap();
+
+        assert_eq!(chip8.i, 0);
+
+        chip8.register[0] = 0x17;
+
+        chip8.run();
+
+        assert_eq!(chip8.pc, 0xF3);
+    }
+
+    #[test]
+    fn test_jump_to_nnn_plus_v0_ignores_vx_by_default() {
+        // 0xBNNN: Jumps to the address NNN plus V0.
+        let program: Vec<u8> = vec![0xB3, 0x00];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[0] = 0x10;
+        chip8.register[3] = 0x20;
+
+        chip8.step();
+
+        assert_eq!(chip8.pc, 0x310);
+    }
+
+    #[test]
+    fn test_jump_quirk_uses_vx() {
+        // 0xBXNN on SUPER-CHIP: Jumps to the address XNN plus VX.
+        let program: Vec<u8> = vec![0xB3, 0x00];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.set_quirks(Quirks::super_chip());
+
+        chip8.register[0] = 0x10;
+        chip8.register[3] = 0x20;
+
+        chip8.step();
+
+        assert_eq!(chip8.pc, 0x320);
+    }
+
+    #[test]
+    fn test_draw_sprite_at_x_y_with_height_n_with_no_collision() {
+        // 0xDXYN: Draws a sprite at coordinate (VX, VY) that has a width of 8 pixels
+        // and a height of N pixels.
+        let start_x = 10;
+        let start_y = 10;
+
+        // Draw the 0 pixel at (10, 10)
+        let program: Vec<u8> = vec![0xD4, 0x65];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        let how_many_ones = chip8.screen.how_many_ones();
+
+        assert_eq!(how_many_ones, 0);
+        assert_eq!(chip8.register[0xF], 0);
+
+        // set i to the first sprite in the font set (the number 0)
+        chip8.i = 0;
+        chip8.register[4] = start_x;
+        chip8.register[6] = start_y;
+
+        chip8.run();
+
+        let how_many_ones = chip8.screen.how_many_ones();
+
+        assert_eq!(how_many_ones, 14);
+        assert_eq!(chip8.register[0xF], 0);
+    }
+
+    #[test]
+    fn test_draw_sprite_at_x_y_with_height_n_with_collision() {
+        // 0xDXYN: Draws a sprite at coordinate (VX, VY) that has a width of 8 pixels
+        // and a height of N pixels.
+        let start_x = 10;
+        let start_y = 10;
+
+        // Draw the 0 pixel at (10, 10), twice, which should result in
+        // 0 pixels being set to 1, and the `chip8.register[0xF]` should be set to 1,
+        // indicating a collistion
+        let program: Vec<u8> = vec![0xD4, 0x65, 0xD4, 0x65];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        let how_many_ones = chip8.screen.how_many_ones();
+
+        assert_eq!(how_many_ones, 0);
+        assert_eq!(chip8.register[0xF], 0);
+
+        // set i to the first sprite in the font set (the number 0)
+        chip8.i = 0;
+        chip8.register[4] = start_x;
+        chip8.register[6] = start_y;
+
+        // This will draw the `0` in the first step, and overwrite said zero at the second step.
+        chip8.run();
+
+        let how_many_ones = chip8.screen.how_many_ones();
+
+        assert_eq!(how_many_ones, 0);
+        assert_eq!(chip8.register[0xF], 1);
+    }
+
+    #[test]
+    fn test_skip_next_instruction_if_key_in_vx_is_pressed_positive() {
+        // 0xEX9E: Skips the next instruction if the key stored in VX is pressed.
+        let key_index: u8 = 0x4;
+        let program: Vec<u8> = vec![0xE4, 0x9E];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        let orig_pc = chip8.pc;
+
+        chip8.register[4] = key_index;
+        chip8.keyboard.keypress(key_index);
+
+        chip8.step();
+
+        assert_eq!(chip8.pc, orig_pc + 4);
+    }
+
+    #[test]
+    fn test_skip_next_instruction_if_key_in_vx_is_pressed_negative() {
+        // 0xEX9E: Skips the next instruction if the key stored in VX is pressed.
+        let key_index: u8 = 0x4;
+        let program: Vec<u8> = vec![0xE4, 0x9E];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        let keys_pressed = chip8.keyboard.any_key_pressed();
+
+        assert!(!keys_pressed);
+
+        let orig_pc = chip8.pc;
+
+        chip8.register[4] = key_index;
+
+        let keys_pressed = chip8.keyboard.any_key_pressed();
+        assert!(!keys_pressed);
+
+        chip8.keyboard.keypress(key_index);
+
+        chip8.step();
+
+        assert_eq!(chip8.pc, orig_pc + 4);
+    }
+
+    #[test]
+    fn test_skip_next_instruction_if_key_in_vx_is_not_pressed_positive() {
+        // 0xEXA1: Skips the next instruction if the key stored in VX isn't pressed.
+        let key_index: u8 = 0x4;
+        let program: Vec<u8> = vec![0xE4, 0xA1];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        let keys_pressed = chip8.keyboard.any_key_pressed();
+
+        assert!(!keys_pressed);
+
+        let orig_pc = chip8.pc;
+
+        chip8.register[4] = key_index;
+
+        chip8.run();
+
+        let keys_pressed = chip8.keyboard.any_key_pressed();
+
+        assert!(!keys_pressed);
+        assert_eq!(chip8.pc, orig_pc + 4);
+    }
+
+    #[test]
+    fn test_skip_next_instruction_if_key_in_vx_is_not_pressed_negative() {
+        // 0xEXA1: Skips the next instruction if the key stored in VX isn't pressed.
+        let key_index: u8 = 0x4;
+        let program: Vec<u8> = vec![0xE4, 0xA1];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        let keys_pressed = chip8.keyboard.any_key_pressed();
+
+        assert!(!keys_pressed);
+
+        let orig_pc = chip8.pc;
+
+        chip8.register[4] = key_index;
+        chip8.keyboard.keypress(key_index);
+
+        chip8.step();
+
+        let keys_pressed = chip8.keyboard.any_key_pressed();
+
+        assert!(keys_pressed);
+        assert_eq!(chip8.pc, orig_pc + 2);
+    }
+
+    #[test]
+    fn test_load_i_with_long_address() {
+        // 0xF000 0xNNNN: Sets I to the 16-bit address NNNN (XO-CHIP).
+        let program: Vec<u8> = vec![
+            0xF0, 0x00, 0x12, 0x34,
+            0x0, 0x0 // exit
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        let orig_pc = chip8.pc;
+
+        chip8.step();
+
+        assert_eq!(chip8.i, 0x1234);
+        assert_eq!(chip8.pc, orig_pc + 4);
+    }
+
+    #[test]
+    fn test_load_i_with_long_address_past_end_of_memory() {
+        let mut chip8 = Chip8::new_with_memory(4096);
+        chip8.load_into_memory_at(vec![0xF0, 0x00], 0xFFE).unwrap();
+
+        assert_eq!(chip8.step(), ExecStatus::Error(Chip8Error::AddressOutOfBounds { addr: 0x1000, len: 2 }));
+        assert_eq!(chip8.pc, 0xFFE);
+    }
+
+    #[test]
+    fn test_set_vx_to_value_of_delay_timer() {
+        // 0xFX07: Sets VX to the value of the delay timer.
+        let test_value: u8 = 23;
+        let program: Vec<u8> = vec![0xF4, 0x07];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.delay_timer, 0);
+
+        chip8.delay_timer = test_value;
+
+        chip8.run();
+
+        assert_eq!(chip8.register[4], test_value);
+    }
+
+    #[test]
+    fn test_wait_for_keypress() {
+        // 0xFX0A: A key press is awaited, and then stored in VX.
+        // (Blocking Operation. All instruction halted until next key event)
+        let key_index: u8 = 0x4;
+        let program: Vec<u8> = vec![
+            0xF4, 0x0A
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.register[4], 0);
+        let orig_pc = chip8.pc;
+
+        // After this, everything should be just as it was,
+        // since no key has been pressed and program counter wasn't incremented
+        chip8.step();
+
+        assert_eq!(chip8.pc, orig_pc);
+
+        // Now set the key, and go again
+        chip8.keyboard.keypress(key_index);
+
+        // After this time, the key index should be in `chip8.register[4]`,
+        // the `chip8.key` array should be all `0`, and `self.pc` should have been advanced
+        chip8.step();
+
+        assert_eq!(chip8.register[4], key_index);
+        assert_eq!(chip8.pc, orig_pc + 2);
+    }
+
+    struct ScriptedInput {
+        keys: VecDeque<u8>,
+    }
+
+    impl InputSource for ScriptedInput {
+        fn is_pressed(&self, key: u8) -> bool {
+            self.keys.front() == Some(&key)
+        }
+
+        fn wait_key(&mut self) -> Option<u8> {
+            self.keys.pop_front()
+        }
+    }
+
+    #[test]
+    fn test_wait_for_keypress_from_input_source() {
+        // 0xFX0A: A key press is awaited, and then stored in VX.
+        let program: Vec<u8> = vec![
+            0xF0, 0x0A,
+            0xF1, 0x0A,
+            0xF2, 0x0A,
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.set_input_source(Box::new(ScriptedInput { keys: VecDeque::from([0x7, 0xC]) }));
+
+        chip8.step();
+        chip8.step();
+
+        assert_eq!(chip8.register[0], 0x7);
+        assert_eq!(chip8.register[1], 0xC);
+
+        // the script ran out of keys, so the machine keeps waiting.
+        let orig_pc = chip8.pc;
+        chip8.step();
+
+        assert_eq!(chip8.pc, orig_pc);
+        assert_eq!(chip8.register[2], 0);
+    }
+
+    #[test]
+    fn test_skip_if_key_pressed_from_input_source() {
+        // 0xEX9E: Skips the next instruction if the key stored in VX is pressed.
+        let program: Vec<u8> = vec![0xE4, 0x9E];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.set_input_source(Box::new(ScriptedInput { keys: VecDeque::from([0xB]) }));
+
+        chip8.register[4] = 0xB;
+        let orig_pc = chip8.pc;
+
+        chip8.step();
+
+        assert_eq!(chip8.pc, orig_pc + 4);
+    }
+
+    #[test]
+    fn test_record_and_replay_input() {
+        let program: Vec<u8> = vec![
+            0xF0, 0x0A, // V0 = key
+            0xF1, 0x0A, // V1 = key
+            0x80, 0x14, // V0 += V1
+        ];
+
+        let mut recorded = create_and_load(&program).unwrap();
+        recorded.start_recording();
+
+        recorded.step();
+        recorded.step();
+        recorded.set_key(0x3, true);
+        recorded.step();
+        recorded.set_key(0x3, false);
+        recorded.step();
+        recorded.set_key(0x5, true);
+        recorded.step();
+        recorded.set_key(0x5, false);
+        recorded.step();
+
+        let events = recorded.take_recording();
+
+        assert_eq!(events, vec![(2, 0x3, true), (3, 0x3, false), (4, 0x5, true), (5, 0x5, false)]);
+        assert_eq!(recorded.register[0], 0x8);
+
+        let mut replayed = create_and_load(&program).unwrap();
+        replayed.replay(events);
+
+        for _ in 0..6 {
+            replayed.step();
+        }
+
+        assert_eq!(replayed.register, recorded.register);
+        assert_eq!(replayed.pc, recorded.pc);
+        assert_eq!(replayed.cycles(), recorded.cycles());
+    }
+
+    #[test]
+    fn test_set_delay_timer_to_vx() {
+        // 0xFX15: Sets the delay timer to VX.
+        let program: Vec<u8> = vec![0xF4, 0x15];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.delay_timer, 0);
+
+        chip8.register[4] = 0x17;
+
+        chip8.run();
+
+        // the value is 1 less than what was set, because
+        // the `process_timers` method has been called
+        assert_eq!(chip8.delay_timer, 0x17 - 1);
+    }
+
+    #[test]
+    fn test_set_sound_timer_to_vx() {
+        // 0xFX18: Sets the delay timer to VX.
+        let program: Vec<u8> = vec![0xF4, 0x18];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.sound_timer, 0);
+
+        chip8.register[4] = 0x17;
+
+        chip8.run();
+
+        // the value is 1 less than what was set, because
+        // the `process_timers` method has been called
+        assert_eq!(chip8.sound_timer, 0x17 - 1);
+    }
+
+    #[test]
+    fn test_beep_count() {
+        let program: Vec<u8> = vec![
+            0x60, 0x02,
+            0xF0, 0x18,
+            0xF0, 0x18, // still beeping, not a new sound
+            0x61, 0x00,
+            0x61, 0x00, // the sound timer ran out
+            0xF0, 0x18,
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.run();
+
+        assert_eq!(chip8.beep_count(), 2);
+    }
+
+    #[test]
+    fn test_add_vx_to_i_with_no_overflow() {
+        // 0xFX1E: Adds VX to I. VF is set to 1 when there is a range overflow (I+VX>0xFFF),
+        // and to 0 when there isn't.
+        let program: Vec<u8> = vec![0xF4, 0x1E];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.i = 0xA;
+        chip8.register[4] = 0x17;
+
+        chip8.run();
+
+        assert_eq!(chip8.i, 0x21);
+        assert_eq!(chip8.register[0xF], 0);
+    }
+
+    #[test]
+    fn test_add_vx_to_i_with_overflow() {
+        // 0xFX1E: Adds VX to I. VF is set to 1 when there is a range overflow (I+VX>0xFFF),
+        // and to 0 when there isn't.
+        let program: Vec<u8> = vec![0xF4, 0x1E];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.i = 0xFFA;
+        chip8.register[4] = 0xA;
+
+        chip8.run();
+
+        assert_eq!(chip8.i, 0x1004);
+        assert_eq!(chip8.register[0xF], 1);
+    }
+
+    #[test]
+    fn test_set_i_to_location_of_sprite_for_character_in_vx() {
+        // 0xFX29: Sets I to the location of the sprite for the character in VX.
+        // Characters 0-F (in hexadecimal) are represented by a 4x5 font.
+        let program: Vec<u8> = vec![0xF4, 0x29];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 2;
+
+        chip8.run();
+
+        assert_eq!(chip8.i, 10);
+    }
+
+    #[test]
+    fn test_set_i_to_location_of_big_sprite_for_digit_in_vx() {
+        // 0xFX30: Sets I to the location of the SUPER-CHIP 8x10 sprite for the digit in VX.
+        let program: Vec<u8> = vec![0xF4, 0x30];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.register[4] = 3;
+
+        chip8.run();
+
+        assert_eq!(chip8.i, 0xA0 + 30);
+        assert_eq!(chip8.memory[chip8.i], SCHIP_BIG_FONTSET[30]);
+        assert_eq!(chip8.memory[chip8.i + 9], SCHIP_BIG_FONTSET[39]);
+    }
+
+    #[test]
+    fn test_big_font_digit_uses_low_nibble_and_only_has_decimal_glyphs() {
+        let program: Vec<u8> = vec![0xF4, 0x30, 0xF5, 0x30];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.register[4] = 0x79;
+        chip8.register[5] = 0x0A;
+
+        chip8.step();
+
+        assert_eq!(chip8.i, BIG_FONTSET_START + 90);
+
+        assert_eq!(chip8.step(), ExecStatus::Error(Chip8Error::NoBigFontGlyph(0xA)));
+        assert_eq!(chip8.i, BIG_FONTSET_START + 90);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 2);
+    }
+
+    #[test]
+    fn test_store_binary_coded_decimal_representation_of_vx() {
+        // 0xFX33: Stores the binary-coded decimal representation of VX, with the most
+        // significant of three digits at the address in I, the middle digit at I plus 1,
+        // and the least significant digit at I plus 2.
+        let program: Vec<u8> = vec![0xF4, 0x33];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        let first_i = LOWER_MEMORY_BOUNDARY;
+        chip8.i = first_i;
+        chip8.register[4] = 0xDC;
+
+        chip8.run();
+
+        assert_eq!(chip8.memory[first_i], 2);
+        assert_eq!(chip8.memory[first_i + 1], 2);
+        assert_eq!(chip8.memory[first_i + 2], 0);
+    }
+
+    #[test]
+    fn test_store_v0_to_vx_in_memory_starting_at_address_i() {
+        // 0xFX55: Stores V0 to VX (including VX) in memory starting at address I.
+        // The offset from I is increased by 1 for each value written, but I itself
+        // is left unmodified.
+
+        let program: Vec<u8> = vec![
+            0xF4, 0x55,
+            0x0, 0x0 // exit
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        let first_i = LOWER_MEMORY_BOUNDARY + 4;
+        chip8.i = first_i;
+
+        for i in 0..5 {
+            chip8.register[i as usize] = i + 1;
+        }
+
+        chip8.run();
+
+        for i in 0..5 {
+            assert_eq!(chip8.memory[first_i + i], (i + 1) as u8);
+        }
+    }
+
+    #[test]
+    fn test_load_store_quirk_increments_i() {
+        let program: Vec<u8> = vec![0xF4, 0x55, 0xF2, 0x65];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.set_quirks(Quirks::cosmac_vip());
+
+        let first_i = LOWER_MEMORY_BOUNDARY + 8;
+        chip8.i = first_i;
+
+        chip8.step();
+
+        assert_eq!(chip8.i, first_i + 5);
+
+        chip8.step();
+
+        assert_eq!(chip8.i, first_i + 8);
+    }
+
+    #[test]
+    fn test_fill_v0_to_vx_with_values_from_memory_starting_at_address_i() {
+        // 0xFX65: Fills V0 to VX (including VX) with values from memory
+        // starting at address I. The offset from I is increased by 1 for
+        // each value written, but I itself is left unmodified.
+        let program: Vec<u8> = vec![
+            0xF4, 0x65,
+            0x0, 0x0 // exit
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        let first_i = LOWER_MEMORY_BOUNDARY + 4;
+        chip8.i = first_i;
+
+        for i in 0..5 {
+            chip8.memory[first_i + i] = i as u8 + 1;
+        }
+
+        chip8.run();
+
+        for i in 0..5 {
+            assert_eq!(chip8.register[i as usize], i + 1);
+        }
+    }
+
+    #[test]
+    fn test_builder_defaults() {
+        let chip8 = Chip8::builder().build();
+
+        assert_eq!(chip8.quirks(), Quirks::default());
+        assert_eq!(chip8.halt_mode(), HaltMode::OnZeroWord);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY);
+    }
+
+    #[test]
+    fn test_halt_mode_never_executes_past_zero_word() {
+        let program: Vec<u8> = vec![
+            0x00, 0x00,
+            0x70, 0x01,
+        ];
+
+        let mut chip8 = Chip8::builder().halt_mode(HaltMode::Never).build();
+        chip8.load_into_memory(program).unwrap();
+
+        chip8.step();
+        chip8.step();
+
+        assert_eq!(chip8.register[0], 1);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 4);
+    }
+
+    #[test]
+    fn test_halt_mode_on_zero_word_stops() {
+        let program: Vec<u8> = vec![
+            0x00, 0x00,
+            0x70, 0x01,
+        ];
+
+        let mut chip8 = Chip8::builder().halt_mode(HaltMode::OnZeroWord).build();
+        chip8.load_into_memory(program).unwrap();
+
+        chip8.run();
+
+        assert_eq!(chip8.register[0], 0);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY);
+    }
+
+    #[test]
+    fn test_halt_mode_on_infinite_jump_stops_at_self_jump() {
+        let program: Vec<u8> = vec![
+            0x00, 0x00, // executed as SYS 0
+            0x70, 0x01,
+            0x12, 0x04, // jump to self
+        ];
+
+        let mut chip8 = Chip8::builder().halt_mode(HaltMode::OnInfiniteJump).build();
+        chip8.load_into_memory(program).unwrap();
+
+        chip8.run();
+
+        assert_eq!(chip8.register[0], 1);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 4);
+        assert_eq!(chip8.cycles(), 2);
+    }
+
+    #[test]
+    fn test_halt_mode_on_infinite_jump_follows_other_jumps() {
+        let program: Vec<u8> = vec![
+            0x12, 0x04, // jump over the next instruction
+            0x70, 0x01,
+            0x12, 0x04,
+        ];
+
+        let mut chip8 = Chip8::builder().halt_mode(HaltMode::OnInfiniteJump).build();
+        chip8.load_into_memory(program).unwrap();
+
+        chip8.run();
+
+        assert_eq!(chip8.register[0], 0);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 4);
+    }
+
+    #[test]
+    fn test_halt_mode_on_infinite_jump_above_4k() {
+        let mut chip8 = Chip8::new_with_memory(0x10000);
+        chip8.halt_mode = HaltMode::OnInfiniteJump;
+        chip8.load_into_memory_at([0x70, 0x01, 0x12, 0x36], 0x234).unwrap();
+        // jumps to 0x234, not to itself
+        chip8.load_into_memory_at([0x12, 0x34], 0x1234).unwrap();
+        chip8.set_pc(0x1234);
+
+        chip8.run();
+
+        assert_eq!(chip8.register[0], 1);
+        assert_eq!(chip8.pc, 0x236);
+    }
+
+    #[test]
+    fn test_beep_is_logged() {
+        crate::test_logger::start_capture();
+
+        let program: Vec<u8> = vec![
+            0x60, 0x01,
+            0xF0, 0x18, // sound timer = 1
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.run();
+
+        let records = crate::test_logger::captured();
+        assert!(records.contains(&(log::Level::Info, "BEEP!".to_string())));
+        assert!(records.contains(&(log::Level::Trace, "0xF018 at 0x202".to_string())));
+    }
+
+    #[test]
+    fn test_subroutine_calls_are_logged() {
+        crate::test_logger::start_capture();
+
+        let program: Vec<u8> = vec![
+            0x22, 0x04, // call 0x204
+            0x00, 0x00,
+            0x00, 0xEE,
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.run();
+
+        let debug: Vec<String> = crate::test_logger::captured()
+            .into_iter()
+            .filter(|(level, _)| *level == log::Level::Debug)
+            .map(|(_, message)| message)
+            .collect();
+        assert_eq!(debug, vec!["call 0x204 from 0x200", "return to 0x200"]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_save_state_round_trip() {
+        let program: Vec<u8> = vec![
+            0x60, 0x05,
+            0xA2, 0x0A,
+            0xF0, 0x15, // delay timer = 5
+            0xD0, 0x05,
+            0x22, 0x0C, // call 0x20C
+            0x00, 0x00,
+            0xFF, 0x00,
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        for _ in 0..5 {
+            chip8.step();
+        }
+
+        let mut save = Vec::new();
+        chip8.save_to_writer(&mut save).unwrap();
+
+        let mut restored = Chip8::new();
+        restored.load_from_reader(&mut std::io::Cursor::new(save)).unwrap();
+
+        assert_eq!(restored.pc, chip8.pc);
+        assert_eq!(restored.i, chip8.i);
+        assert_eq!(restored.register, chip8.register);
+        assert_eq!(restored.stack, chip8.stack);
+        assert_eq!(restored.sp, 1);
+        assert_eq!(restored.delay_timer, chip8.delay_timer);
+        assert_eq!(restored.cycles(), 5);
+        assert_eq!(restored.read_byte(0x20C), Some(0xFF));
+        assert_eq!(restored.screen.to_string(), chip8.screen.to_string());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_load_state_rejects_other_data() {
+        let mut chip8 = Chip8::new();
+
+        let error = chip8.load_from_reader(&mut std::io::Cursor::new(b"not a save state")).unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY);
+    }
+
+    #[test]
+    fn test_draw_written_sprite() {
+        let program: Vec<u8> = vec![0xD0, 0x05];
+        // the glyph of a 0
+        let glyph = [0xF0, 0x90, 0x90, 0x90, 0xF0];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.write_sprite(0x300, &glyph).unwrap();
+        chip8.set_index_register(0x300);
+
+        chip8.step();
+
+        for (y, row) in glyph.iter().enumerate() {
+            for x in 0..8 {
+                let lit = row & (0x80 >> x) != 0;
+                assert_eq!(chip8.screen.get_pixel_color(x, y), lit as u8);
+            }
+        }
+    }
+
+    #[test]
+    fn test_collision_counts_rows() {
+        let program: Vec<u8> = vec![
+            0xA2, 0x08,
+            0xD0, 0x03, // three rows, the last one is empty
+            0xD0, 0x03,
+            0x00, 0x00,
+            0x80, 0x80, 0x00,
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.set_quirks(Quirks { collision_counts_rows: true, ..Quirks::default() });
+
+        chip8.run();
+
+        assert_eq!(chip8.register[0xF], 2);
+    }
+
+    #[test]
+    fn test_classic_collision_is_a_flag() {
+        let program: Vec<u8> = vec![
+            0xA2, 0x08,
+            0xD0, 0x03,
+            0xD0, 0x03,
+            0x00, 0x00,
+            0x80, 0x80, 0x00,
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.run();
+
+        assert_eq!(chip8.register[0xF], 1);
+    }
+
+    #[test]
+    fn test_collision_flag_is_per_draw() {
+        let program: Vec<u8> = vec![
+            0xA2, 0x0C,
+            0xD0, 0x02,
+            0xD0, 0x02, // erases the first draw
+            0x60, 0x20,
+            0xD0, 0x02, // on empty space
+            0x00, 0x00,
+            0xC0, 0x0C,
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.run_frame(3);
+
+        assert_eq!(chip8.register[0xF], 1);
+        assert_eq!(chip8.screen.how_many_ones(), 0);
+
+        chip8.run_frame(2);
+
+        assert_eq!(chip8.register[0xF], 0);
+        assert_eq!(chip8.screen.how_many_ones(), 4);
+    }
+
+    #[test]
+    fn test_collision_flag_is_not_reset_per_row() {
+        let program: Vec<u8> = vec![
+            0xA2, 0x0A,
+            0xD0, 0x01,
+            0x61, 0x00,
+            0xDF, 0x12, // the collision is in the first row only, VF is also the x coordinate
+            0x00, 0x00,
+            0x80, 0x01,
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.register[0xF] = 0;
+        chip8.run();
+
+        assert_eq!(chip8.register[0xF], 1);
+        assert_eq!(chip8.screen.how_many_ones(), 1);
+        assert_eq!(chip8.screen.get_pixel_color(7, 1), 1);
+    }
+
+    #[test]
+    fn test_status_waiting_for_key() {
+        let program: Vec<u8> = vec![0xF3, 0x0A];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.status(), ExecStatus::Running);
+
+        chip8.step();
+
+        assert_eq!(chip8.status(), ExecStatus::WaitingForKey);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY);
+    }
+
+    #[test]
+    fn test_status_halted() {
+        let program: Vec<u8> = vec![0x70, 0x01];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.step();
+        assert_eq!(chip8.status(), ExecStatus::Running);
+
+        chip8.step();
+        assert_eq!(chip8.status(), ExecStatus::Halted);
+
+        chip8.reset();
+        assert_eq!(chip8.status(), ExecStatus::Running);
+    }
+
+    #[test]
+    fn test_run_stops_at_unknown_opcode() {
+        let program: Vec<u8> = vec![0x70, 0x01, 0x50, 0x01];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        chip8.run();
+
+        assert_eq!(chip8.status(), ExecStatus::Error(Chip8Error::UnknownOpcode(0x5001)));
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 2);
+    }
+
+    #[test]
+    fn test_step_reports_unknown_opcode() {
+        let program: Vec<u8> = vec![0x50, 0x01, 0x80, 0x08];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.step(), ExecStatus::Error(Chip8Error::UnknownOpcode(0x5001)));
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY);
+
+        chip8.set_pc(LOWER_MEMORY_BOUNDARY + 2);
+
+        assert_eq!(chip8.step(), ExecStatus::Error(Chip8Error::UnknownOpcode(0x8008)));
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 2);
+    }
+
+    #[test]
+    fn test_step_reports_waiting_for_key() {
+        let program: Vec<u8> = vec![0xF3, 0x0A, 0x73, 0x01];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+
+        assert_eq!(chip8.step(), ExecStatus::WaitingForKey);
+        assert_eq!(chip8.step(), ExecStatus::WaitingForKey);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY);
+
+        chip8.set_key(0x5, true);
+
+        assert_eq!(chip8.step(), ExecStatus::Running);
+        assert_eq!(chip8.register[3], 0x5);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 2);
+
+        assert_eq!(chip8.step(), ExecStatus::Running);
+        assert_eq!(chip8.register[3], 0x6);
+    }
+
+    #[test]
+    fn test_execute_opcode_does_not_tick_timers() {
+        let mut chip8 = Chip8::new();
+        chip8.delay_timer = 10;
+
+        chip8.execute_opcode(0x6005);
+
+        assert_eq!(chip8.register[0], 5);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 2);
+        assert_eq!(chip8.delay_timer, 10);
+        assert_eq!(chip8.cycles(), 0);
+
+        chip8.execute_instruction(0x6006);
+
+        assert_eq!(chip8.delay_timer, 9);
+        assert_eq!(chip8.cycles(), 1);
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let program: Vec<u8> = vec![
+            0xF0, 0x15, // delay timer = V0
+            0x70, 0x01,
+            0x70, 0x01,
+        ];
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.register[0] = 10;
+        chip8.step();
+
+        chip8.pause();
 
-        let orig_pc = chip8.pc;
+        assert!(chip8.is_paused());
+        assert_eq!(chip8.step(), ExecStatus::Paused);
+        chip8.run_frame(10);
+        assert_eq!(chip8.status(), ExecStatus::Paused);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 2);
+        assert_eq!(chip8.delay_timer, 9);
+        assert_eq!(chip8.register[0], 10);
 
-        chip8.run();
+        chip8.resume();
 
-        assert_eq!(chip8.pc, orig_pc + 2);
+        assert!(!chip8.is_paused());
+        assert_eq!(chip8.step(), ExecStatus::Running);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 4);
+        assert_eq!(chip8.delay_timer, 8);
+        assert_eq!(chip8.register[0], 11);
     }
 
     #[test]
-    fn test_skip_next_instruction_if_vx_does_not_equal_nn_positive() {
-        // 0x4XNN: Skips the next instruction if VX doesn't equal NN.
-        let program: Vec<u8> = vec![0x44, 0x17];
+    fn test_add_vx_to_i_past_address_space() {
+        let program: Vec<u8> = vec![0xF0, 0x1E];
 
         let mut chip8 = create_and_load(&program).unwrap();
+        chip8.i = 0xFF0;
+        chip8.register[0] = 0x20;
 
-        chip8.register[4] = 0x23;
-
-        let orig_pc = chip8.pc;
-
-        chip8.run();
+        chip8.step();
 
-        assert_eq!(chip8.pc, orig_pc + 4);
+        assert_eq!(chip8.i, 0x1010);
     }
 
     #[test]
-    fn test_skip_next_instruction_if_vx_does_not_equal_nn_negative() {
-        // 0x4XNN: Skips the next instruction if VX doesn't equal NN.
-        let program: Vec<u8> = vec![0x44, 0x17];
+    fn test_add_vx_to_i_wraps_with_quirk() {
+        let program: Vec<u8> = vec![0xF0, 0x1E];
 
         let mut chip8 = create_and_load(&program).unwrap();
+        chip8.set_quirks(Quirks { wrap_index_register: true, ..Quirks::default() });
+        chip8.i = 0xFF0;
+        chip8.register[0] = 0x20;
 
-        chip8.register[4] = 0x17;
-
-        let orig_pc = chip8.pc;
+        chip8.step();
 
-        chip8.run();
+        assert_eq!(chip8.i, 0x010);
+        assert_eq!(chip8.register[0xF], 1);
+    }
 
-        assert_eq!(chip8.pc, orig_pc + 2);
+    #[test]
+    fn test_new_for_rom() {
+        let rom: &[u8] = &[0x60, 0x05];
+
+        let default_size = Memory::default().size();
+
+        for (platform, quirks, memory_size) in [
+            (Platform::CosmacVip, Quirks::cosmac_vip(), default_size),
+            (Platform::SuperChip, Quirks::super_chip(), default_size),
+            (Platform::XoChip, Quirks::xo_chip(), 0x10000),
+            (Platform::Modern, Quirks::modern(), default_size),
+        ] {
+            let chip8 = Chip8::new_for_rom(rom, platform).unwrap();
+
+            assert_eq!(chip8.quirks(), quirks);
+            assert_eq!(chip8.memory().size(), memory_size);
+            assert_eq!(chip8.peek_opcode(), 0x6005);
+        }
     }
 
     #[test]
-    fn test_skip_next_instruction_if_vx_equals_vy_positive() {
-        // 0x5XY0: Skips the next instruction if VX equals VY.
-        let program: Vec<u8> = vec![0x54, 0x60];
+    fn test_rom_checksum() {
+        let program: Vec<u8> = vec![0x60, 0x05, 0x70, 0x01];
 
         let mut chip8 = create_and_load(&program).unwrap();
 
-        chip8.register[4] = 0x17;
-        chip8.register[6] = 0x17;
-
-        let orig_pc = chip8.pc;
+        assert_eq!(chip8.rom_checksum(), 0xAC64_ED81);
 
-        chip8.run();
+        chip8.load_into_memory_at(program, 0x600).unwrap();
 
-        assert_eq!(chip8.pc, orig_pc + 4);
+        assert_eq!(chip8.rom_checksum(), 0xAC64_ED81);
     }
 
     #[test]
-    fn test_skip_next_instruction_if_vx_equals_vy_negative() {
-        // 0x5XY0: Skips the next instruction if VX equals VY.
-        let program: Vec<u8> = vec![0x54, 0x60];
+    fn test_take_redraw_after_draw() {
+        let program: Vec<u8> = vec![
+            0x70, 0x01,
+            0xD0, 0x05,
+            0x00, 0xE0,
+        ];
 
         let mut chip8 = create_and_load(&program).unwrap();
 
-        chip8.register[4] = 0x17;
-        chip8.register[6] = 0x23;
-
-        let orig_pc = chip8.pc;
+        chip8.step();
+        assert!(!chip8.take_redraw());
 
-        chip8.run();
+        chip8.step();
+        assert!(chip8.take_redraw());
+        assert!(!chip8.take_redraw());
 
-        assert_eq!(chip8.pc, orig_pc + 2);
+        chip8.step();
+        assert!(chip8.take_redraw());
     }
 
     #[test]
-    fn test_set_vx_to_nn() {
-        // 0x6XNN: Sets VX to NN.
-        let program: Vec<u8> = vec![0x64, 0xAA];
-
-        let chip8 = create_and_load(&program).unwrap();
-
-        assert_eq!(chip8.register[4], 0);
+    fn test_run_with_callback_stops_when_callback_returns_false() {
+        let program: Vec<u8> = vec![
+            0x70, 0x01,
+            0x30, 0x05, // skip the jump back once V0 == 5
+            0x12, 0x00,
+            0xD0, 0x15,
+            0x12, 0x08, // loop forever
+        ];
 
         let mut chip8 = create_and_load(&program).unwrap();
+        let mut frames = 0;
 
-        chip8.run();
+        chip8.run_with_callback(3, |chip8| {
+            frames += 1;
+            chip8.screen().how_many_ones() == 0
+        });
 
-        assert_eq!(chip8.register[4], 0xAA);
+        assert_eq!(chip8.register[0], 5);
+        assert!(chip8.screen().how_many_ones() > 0);
+        assert_eq!(frames, 5);
     }
 
     #[test]
-    fn test_add_nn_to_vx() {
-        // 0x7XNN: Adds NN to VX. (Carry flag is not changed)
-        let program: Vec<u8> = vec![0x74, 0xAA];
+    fn test_run_with_callback_stops_when_halted() {
+        let program: Vec<u8> = vec![0x70, 0x01];
 
         let mut chip8 = create_and_load(&program).unwrap();
+        let mut frames = 0;
 
-        chip8.register[4] = 0x10;
-
-        chip8.run();
+        chip8.run_with_callback(10, |_| {
+            frames += 1;
+            true
+        });
 
-        assert_eq!(chip8.register[4], 0xBA);
+        assert_eq!(frames, 1);
+        assert_eq!(chip8.status(), ExecStatus::Halted);
     }
 
     #[test]
-    fn test_add_nn_to_vx_wrapping() {
-        // 0x7XNN: Adds NN to VX. (Carry flag is not changed)
-        let program: Vec<u8> = vec![0x74, 0xAA, 0x0, 0x0];
+    fn test_font_address_uses_low_nibble_of_vx() {
+        let program: Vec<u8> = vec![0xF3, 0x29];
 
         let mut chip8 = create_and_load(&program).unwrap();
+        chip8.register[3] = 0x2A;
 
-        chip8.register[4] = 0xBA;
-
-        chip8.run();
+        chip8.step();
 
-        assert_eq!(chip8.register[4], 0x64);
+        assert_eq!(chip8.i, FONTSET_START + 0xA * 5);
+        let glyph: Vec<u8> = (0..5).map(|offset| chip8.memory[chip8.i + offset]).collect();
+        assert_eq!(glyph, vec![0xF0, 0x90, 0xF0, 0x90, 0x90]);
     }
 
     #[test]
-    fn test_set_vx_to_value_of_vy() {
-        // 0x8XY0: Sets VX to the value of VY.
-        let program: Vec<u8> = vec![0x84, 0x50];
+    fn test_load_ihex() {
+        let hex = ":040200006005700124\n:02030000F0907B\n:00000001FF\n";
 
-        let mut chip8 = create_and_load(&program).unwrap();
+        let mut chip8 = Chip8::new();
+        chip8.load_ihex(hex).unwrap();
 
-        chip8.register[4] = 0xBA;
-        chip8.register[5] = 0xDD;
+        assert_eq!(chip8.read_byte(0x200), Some(0x60));
+        assert_eq!(chip8.read_byte(0x203), Some(0x01));
+        assert_eq!(chip8.read_byte(0x300), Some(0xF0));
+        assert_eq!(chip8.read_byte(0x301), Some(0x90));
 
         chip8.run();
 
-        assert_eq!(chip8.register[4], 0xDD);
-        assert_eq!(chip8.register[5], 0xDD);
+        assert_eq!(chip8.register[0], 6);
     }
 
     #[test]
-    fn test_set_vx_to_vx_or_vy() {
-        // 0x8XY1: Sets VX to VX or VY. (Bitwise OR operation)
-        let program: Vec<u8> = vec![0x84, 0x51];
-
-        let mut chip8 = create_and_load(&program).unwrap();
-
-        chip8.register[4] = 0xBA;
-        chip8.register[5] = 0xCC;
+    fn test_load_ihex_rejects_bad_checksum() {
+        let hex = ":040200006005700124\n:02030000F0907C\n:00000001FF\n";
 
-        chip8.run();
+        let mut chip8 = Chip8::new();
+        let result = chip8.load_ihex(hex);
 
-        assert_eq!(chip8.register[4], 0xFE);
-        assert_eq!(chip8.register[5], 0xCC);
+        assert_eq!(result, Err(Chip8Error::InvalidIntelHex { line: 2, reason: "bad checksum" }));
+        assert_eq!(chip8.read_byte(0x200), Some(0x0));
     }
 
     #[test]
-    fn test_set_vx_to_vx_and_vy() {
-        // 0x8XY2: Sets VX to VX and VY. (Bitwise AND operation)
-        let program: Vec<u8> = vec![0x84, 0x52];
+    fn test_sprites_clip_at_the_edges() {
+        let program: Vec<u8> = vec![
+            0x60, 0x3C, // V0 = 60
+            0x61, 0x1F, // V1 = 31
+            0xA2, 0x0A,
+            0xD0, 0x12,
+            0x00, 0x00,
+            0xFF, 0xFF,
+        ];
 
         let mut chip8 = create_and_load(&program).unwrap();
 
-        chip8.register[4] = 0xBA;
-        chip8.register[5] = 0xCC;
-
         chip8.run();
 
-        assert_eq!(chip8.register[4], 0x88);
-        assert_eq!(chip8.register[5], 0xCC);
+        assert!((60..64).all(|x| chip8.screen().get_pixel_color(x, 31) == 1));
+        assert_eq!(chip8.screen().get_pixel_color(0, 31), 0);
+        assert_eq!(chip8.screen().get_pixel_color(60, 0), 0);
+        assert_eq!(chip8.screen().how_many_ones(), 4);
     }
 
     #[test]
-    fn test_set_vx_to_vx_xor_vy() {
-        // 0x8XY3: Sets VX to VX xor VY.
-        let program: Vec<u8> = vec![0x84, 0x53];
+    fn test_sprites_wrap_without_clip_sprites() {
+        let program: Vec<u8> = vec![
+            0x60, 0x3C, // V0 = 60
+            0x61, 0x1F, // V1 = 31
+            0xA2, 0x0A,
+            0xD0, 0x12,
+            0x00, 0x00,
+            0xFF, 0xFF,
+        ];
 
         let mut chip8 = create_and_load(&program).unwrap();
-
-        chip8.register[4] = 0xBA;
-        chip8.register[5] = 0xCC;
+        chip8.set_quirks(Quirks { clip_sprites: false, ..Quirks::default() });
 
         chip8.run();
 
-        assert_eq!(chip8.register[4], 0x76);
-        assert_eq!(chip8.register[5], 0xCC);
+        assert!((0..4).chain(60..64).all(|x| chip8.screen().get_pixel_color(x, 31) == 1 && chip8.screen().get_pixel_color(x, 0) == 1));
+        assert_eq!(chip8.screen().how_many_ones(), 16);
     }
 
     #[test]
-    fn test_add_vy_to_vx_with_carry() {
-        // 0x8XY4: Adds VY to VX. VF is set to 1 when there's a carry, and to 0 when there isn't.
-        let program: Vec<u8> = vec![0x84, 0x54];
+    fn test_key_up_and_clear_keys() {
+        let mut chip8 = Chip8::new();
 
-        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.key_down(0x1);
+        chip8.key_down(0xA);
+        chip8.key_up(0x1);
 
-        assert_eq!(chip8.register[0xF], 0);
+        assert!(!chip8.keyboard.was_key_pressed(0x1));
+        assert!(chip8.keyboard.was_key_pressed(0xA));
 
-        chip8.register[4] = 0xBA;
-        chip8.register[5] = 0xCC;
+        chip8.clear_keys();
 
-        chip8.run();
+        assert!(!chip8.keyboard.any_key_pressed());
+    }
 
-        assert_eq!(chip8.register[4], 0x86);
-        assert_eq!(chip8.register[0xF], 1);
+    #[test]
+    fn test_new_with_memory() {
+        let mut chip8 = Chip8::new_with_memory(65536);
+
+        chip8.load_into_memory_at(vec![0x60, 0x05], 0x1000).unwrap();
+        chip8.set_pc(0x1000);
+        chip8.step();
+
+        assert_eq!(chip8.register[0], 0x05);
+        assert_eq!(chip8.memory().size(), 65536);
+        assert_eq!(chip8.load_into_memory_at(vec![0; 0xF001], 0x1000), Err(Chip8Error::ProgramTooLarge { program_len: 0xF001, available: 0xF000 }));
     }
 
     #[test]
-    fn test_add_vy_to_vx_without_carry() {
-        // 0x8XY4: Adds VY to VX. VF is set to 1 when there's a carry, and to 0 when there isn't.
-        let program: Vec<u8> = vec![0x84, 0x54];
+    fn test_sys_is_a_no_op() {
+        let program: Vec<u8> = vec![0x01, 0x23];
 
         let mut chip8 = create_and_load(&program).unwrap();
 
-        assert_eq!(chip8.register[0xF], 0);
+        assert_eq!(chip8.step(), ExecStatus::Running);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 2);
+    }
 
-        chip8.register[4] = 0xBA;
-        chip8.register[5] = 0x10;
+    #[test]
+    fn test_sys_hook() {
+        let program: Vec<u8> = vec![0x01, 0x23, 0x0A, 0xBC];
+        let calls = Arc::new(std::sync::Mutex::new(Vec::new()));
 
-        chip8.run();
+        let mut chip8 = create_and_load(&program).unwrap();
+        let hook_calls = Arc::clone(&calls);
+        chip8.set_sys_hook(Box::new(move |nnn| hook_calls.lock().unwrap().push(nnn)));
+        chip8.step();
+        chip8.step();
 
-        assert_eq!(chip8.register[4], 0xCA);
-        assert_eq!(chip8.register[0xF], 0);
+        assert_eq!(*calls.lock().unwrap(), vec![0x123, 0xABC]);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 4);
     }
 
     #[test]
-    fn test_subtract_vy_from_vx_with_borrow() {
-        // 0x8XY5: VY is subtracted from VX. VF is set to 0 when there's a borrow,
-        // and 1 when there isn't.
-        let program: Vec<u8> = vec![0x84, 0x55];
+    fn test_tick_timers_by() {
+        let mut chip8 = Chip8::new();
+        chip8.delay_timer = 10;
+        chip8.sound_timer = 2;
 
-        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.tick_timers_by(3);
 
-        chip8.register[4] = 0xBA;
-        chip8.register[5] = 0xCC;
+        assert_eq!(chip8.delay_timer, 7);
+        assert_eq!(chip8.sound_timer, 0);
 
-        chip8.run();
+        chip8.tick_timers();
 
-        assert_eq!(chip8.register[4], 0x11);
-        assert_eq!(chip8.register[0xF], 0);
+        assert_eq!(chip8.delay_timer, 6);
     }
 
     #[test]
-    fn test_store_least_significant_bit_of_vx_in_vf_and_shift_vx_right_by_1() {
-        // 0x8XY6: Stores the least significant bit of VX in VF and then shifts VX to
-        // the right by 1.
-        let program: Vec<u8> = vec![0x84, 0x56];
+    fn test_run_frame_ticks_timers_once() {
+        let program: Vec<u8> = vec![
+            0x60, 0x0A,
+            0xF0, 0x15, // DT = 10
+            0x70, 0x01,
+            0x12, 0x04, // loop on the add
+        ];
 
         let mut chip8 = create_and_load(&program).unwrap();
 
-        chip8.register[4] = 0xBB;
-        chip8.register[0xF] = 0x0;
+        chip8.run_frame(20);
 
-        chip8.run();
+        assert_eq!(chip8.delay_timer, 9);
 
-        assert_eq!(chip8.register[4], 0x5D);
-        assert_eq!(chip8.register[0xF], 1);
+        chip8.run_frame(20);
+
+        assert_eq!(chip8.delay_timer, 8);
     }
 
     #[test]
-    fn test_set_vx_to_vy_minus_vx_with_borrow() {
-        // 0x8XY7: Sets VX to VY minus VX. VF is set to 0 when there's a borrow, and 1
-        // when there isn't.
-        let program: Vec<u8> = vec![0x84, 0x57];
+    fn test_debug_string() {
+        let program: Vec<u8> = vec![0x6A, 0x42, 0x22, 0x06, 0x00, 0x00, 0xA1, 0x23];
 
         let mut chip8 = create_and_load(&program).unwrap();
+        chip8.step();
 
-        chip8.register[4] = 0xCC;
-        chip8.register[5] = 0xBA;
+        assert!(chip8.debug_string().contains("Stack top: empty"));
 
-        chip8.run();
+        chip8.step();
+        chip8.step();
+        let dump = chip8.debug_string();
 
-        assert_eq!(chip8.register[4], 0x11);
-        assert_eq!(chip8.register[0xF], 0);
+        assert!(dump.contains("PC: 0x0208"));
+        assert!(dump.contains("I: 0x0123"));
+        assert!(dump.contains("SP: 1"));
+        assert!(dump.contains("VA: 42"));
+        assert!(dump.contains("DT: 00  ST: 00"));
+        assert!(dump.contains("Stack top: 0x0202"));
+        assert_eq!(dump.lines().count(), 5);
     }
 
     #[test]
-    fn test_store_most_significant_bit_of_vx_in_vf_and_shift_vx_right_by_1() {
-        // 0x8XYE: Stores the most significant bit of VX in VF and then shifts VX to the left by 1.
-        let program: Vec<u8> = vec![0x84, 0x5E];
+    fn test_populate_register_ignores_extra_values() {
+        let mut chip8 = Chip8::new();
 
-        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.populate_register((1..=20).collect());
 
-        chip8.register[4] = 0xF0;
-        chip8.register[0xF] = 0x0;
+        assert_eq!(chip8.register, core::array::from_fn(|index| index as u8 + 1));
+    }
+
+    #[test]
+    fn test_no_opcode_panics() {
+        for opcode in 0..=u16::MAX {
+            Chip8::new().execute_instruction(opcode);
+
+            // everything at the edge: I and pc at the end of memory, a full stack.
+            let mut chip8 = Chip8::new();
+            chip8.i = 0xFFF;
+            chip8.pc = 0xFFE;
+            chip8.sp = STACK;
+            chip8.register = [0xFF; REGISTER];
+            chip8.execute_instruction(opcode);
+
+            let mut chip8 = Chip8::new_with_memory(0x10000);
+            chip8.i = 0xFFFF;
+            chip8.pc = 0xFFFE;
+            chip8.register = [0xFF; REGISTER];
+            chip8.execute_instruction(opcode);
+        }
+    }
+
+    #[test]
+    fn test_stack_overflow_is_an_error() {
+        let program: Vec<u8> = vec![0x22, 0x00];
 
+        let mut chip8 = create_and_load(&program).unwrap();
         chip8.run();
 
-        assert_eq!(chip8.register[4], 0xE0);
-        assert_eq!(chip8.register[0xF], 1);
+        assert_eq!(chip8.status(), ExecStatus::Error(Chip8Error::StackOverflow));
+        assert_eq!(chip8.sp, STACK);
+        // stepping again reports the same error instead of panicking.
+        assert_eq!(chip8.step(), ExecStatus::Error(Chip8Error::StackOverflow));
     }
 
     #[test]
-    fn test_skip_next_instruction_if_vx_does_not_equal_vy_positive() {
-        // 0x9XY0: Skips the next instruction if VX doesn't equal VY. (Usually the next
-        // instruction is a jump to skip a code block)
-        let program: Vec<u8> = vec![0x94, 0x60];
+    fn test_stack_underflow_is_an_error() {
+        let program: Vec<u8> = vec![0x00, 0xEE];
 
         let mut chip8 = create_and_load(&program).unwrap();
 
-        chip8.register[4] = 0x23;
-        chip8.register[6] = 0x17;
+        assert_eq!(chip8.step(), ExecStatus::Error(Chip8Error::StackUnderflow));
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY);
+    }
 
-        let orig_pc = chip8.pc;
+    #[test]
+    fn test_store_past_end_of_memory_is_an_error() {
+        let program: Vec<u8> = vec![0xAF, 0xFE, 0xF3, 0x55];
 
+        let mut chip8 = Chip8::new_with_memory(4096);
+        chip8.load_into_memory(program).unwrap();
         chip8.run();
 
-        assert_eq!(chip8.pc, orig_pc + 4);
+        assert_eq!(chip8.status(), ExecStatus::Error(Chip8Error::AddressOutOfBounds { addr: 0xFFE, len: 4 }));
+        assert_eq!(chip8.read_byte(0xFFE), Some(0));
     }
 
+    #[cfg(feature = "image")]
     #[test]
-    fn test_skip_next_instruction_if_vx_does_not_equal_vy_negative() {
-        // 0x9XY0: Skips the next instruction if VX doesn't equal VY. (Usually the next
-        // instruction is a jump to skip a code block)
-        let program: Vec<u8> = vec![0x94, 0x60];
+    fn test_save_screen_png() {
+        // draw the "0" glyph in the top left corner.
+        let program: Vec<u8> = vec![0xA0, 0x00, 0xD0, 0x05];
+        let path = std::env::temp_dir().join("chip8_test_save_screen_png.png");
 
         let mut chip8 = create_and_load(&program).unwrap();
+        chip8.run();
+        chip8.save_screen_png(&path, 4).unwrap();
 
-        chip8.register[4] = 0x17;
-        chip8.register[6] = 0x17;
+        let image = image::open(&path).unwrap().into_luma8();
+        fs::remove_file(&path).unwrap();
 
-        let orig_pc = chip8.pc;
+        assert_eq!(image.dimensions(), (64 * 4, 32 * 4));
+        assert_eq!(image.get_pixel(0, 0).0, [0xFF]);
+        // the hole in the middle of the "0"
+        assert_eq!(image.get_pixel(4 + 1, 4 + 1).0, [0x00]);
+        assert_eq!(image.get_pixel(4 * 4, 0).0, [0x00]);
+    }
 
-        chip8.run();
+    #[test]
+    fn test_run_frames_returns_the_screen() {
+        let program: Vec<u8> = vec![
+            0x60, 0x00,
+            0xF0, 0x29, // I = sprite of "0"
+            0xD0, 0x05,
+            0x12, 0x06, // loop forever
+        ];
+        let glyph = [0xF0, 0x90, 0x90, 0x90, 0xF0];
 
-        assert_eq!(chip8.pc, orig_pc + 2);
+        let mut chip8 = create_and_load(&program).unwrap();
+        let screen = chip8.run_frames(10, 8);
+
+        for (y, row) in glyph.iter().enumerate() {
+            for x in 0..8 {
+                assert_eq!(screen.get_pixel_color(x, y), (row >> (7 - x)) & 1, "pixel ({x}, {y})");
+            }
+        }
+        assert_eq!(screen.how_many_ones(), 14);
+        assert_eq!(chip8.cycles(), 80);
     }
 
     #[test]
-    fn test_set_i_to_address_nnn() {
-        // 0xANNN: sets I to the address NNN
-        let program: Vec<u8> = vec![0xA0, 0xDC];
+    fn test_return_to_address_outside_of_memory() {
+        let program: Vec<u8> = vec![0x00, 0xEE];
 
-        let mut chip8 = create_and_load(&program).unwrap();
+        let mut chip8 = Chip8::new_with_memory(4096);
+        chip8.load_into_memory(program).unwrap();
+        chip8.stack[0] = 0x1FFE;
+        chip8.sp = 1;
 
-        assert_eq!(chip8.i, 0);
+        assert_eq!(chip8.step(), ExecStatus::Error(Chip8Error::InvalidReturnAddress(0x1FFE)));
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY);
+        assert_eq!(chip8.sp, 1);
+    }
+
+    #[test]
+    fn test_load_segments() {
+        let launcher: &[u8] = &[0x13, 0x00];
+        let game: &[u8] = &[0x60, 0x05, 0x00, 0x00];
 
+        let mut chip8 = Chip8::new();
+        chip8.load_segments(&[(0x200, launcher), (0x300, game)]).unwrap();
         chip8.run();
 
-        assert_eq!(chip8.i, 0xDC);
+        assert_eq!(chip8.read_byte(0x200), Some(0x13));
+        assert_eq!(chip8.read_byte(0x301), Some(0x05));
+        assert_eq!(chip8.register[0], 0x05);
     }
 
     #[test]
-    fn test_jump_to_nnn_plus_v0() {
-        // 0xBNNN: Jumps to the address NNN plus V0.
-        let program: Vec<u8> = vec![0xB0, 0xDC];
+    fn test_load_overlapping_segments() {
+        let mut chip8 = Chip8::new();
 
-        let mut chip8 = create_and_load(&program).unwrap();
+        let result = chip8.load_segments(&[(0x300, &[0x01, 0x02]), (0x200, &[0xAA; 0x101])]);
 
-        assert_eq!(chip8.i, 0);
+        assert_eq!(result, Err(Chip8Error::OverlappingSegments { addr: 0x300 }));
+        assert_eq!(chip8.read_byte(0x200), Some(0x00));
+    }
 
-        chip8.register[0] = 0x17;
+    #[test]
+    fn test_pixel_callback() {
+        let program: Vec<u8> = vec![
+            0x60, 0x02,
+            0x61, 0x03,
+            0xA2, 0x0E,
+            0xD0, 0x11,
+            0xD0, 0x11, // erases the sprite again
+            0x00, 0xE0, // nothing left to clear
+            0x00, 0x00,
+            0xC0,
+        ];
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
 
+        let mut chip8 = create_and_load(&program).unwrap();
+        let callback_events = Arc::clone(&events);
+        chip8.set_pixel_callback(Box::new(move |x, y, lit| callback_events.lock().unwrap().push((x, y, lit))));
         chip8.run();
 
-        assert_eq!(chip8.pc, 0xF3);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![(2, 3, true), (3, 3, true), (2, 3, false), (3, 3, false)]
+        );
     }
 
     #[test]
-    fn test_draw_sprite_at_x_y_with_height_n_with_no_collision() {
-        // 0xDXYN: Draws a sprite at coordinate (VX, VY) that has a width of 8 pixels
-        // and a height of N pixels.
-        let height = 5;
-        let start_x = 10;
-        let start_y = 10;
-
-        // Draw the 0 pixel at (10, 10)
-        let program: Vec<u8> = vec![0xD4, 0x65];
+    fn test_pixel_callback_on_clear_screen() {
+        let program: Vec<u8> = vec![0xA0, 0x00, 0xD0, 0x01, 0x00, 0xE0];
 
         let mut chip8 = create_and_load(&program).unwrap();
+        chip8.step();
+        chip8.step();
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let callback_events = Arc::clone(&events);
+        chip8.set_pixel_callback(Box::new(move |x, y, lit| callback_events.lock().unwrap().push((x, y, lit))));
+        chip8.step();
+
+        assert_eq!(*events.lock().unwrap(), (0..4).map(|x| (x, 0, false)).collect::<Vec<_>>());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_run_realtime() {
+        let program: Vec<u8> = vec![
+            0x70, 0x01,
+            0x30, 0xFF,
+            0x12, 0x00, // until V0 reaches 255
+        ];
 
-        let how_many_ones = chip8.screen.how_many_ones();
+        // 300 instructions per frame, the 764 of the program take 3 frames.
+        let mut chip8 = Chip8::builder().clock_hz(300 * 60).build();
+        chip8.load_into_memory(program).unwrap();
+        let start = std::time::Instant::now();
+        chip8.run_realtime();
+        let elapsed = start.elapsed();
+
+        assert_eq!(chip8.status(), ExecStatus::Halted);
+        assert_eq!(chip8.cycles(), 764);
+        // two full frames were slept through, the last one ended with the halt.
+        assert!(elapsed >= Duration::from_millis(33), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(1), "{elapsed:?}");
+    }
 
-        assert_eq!(how_many_ones, 0);
-        assert_eq!(chip8.register[0xF], 0);
+    #[test]
+    fn test_set_registers() {
+        let mut chip8 = Chip8::new();
 
-        // set i to the first sprite in the font set (the number 0)
-        chip8.i = 0;
-        chip8.register[4] = start_x;
-        chip8.register[6] = start_y;
+        chip8.set_registers(&[(4, 0x17), (6, 0x23)]).unwrap();
 
-        chip8.run();
+        let mut expected = [0; REGISTER];
+        expected[4] = 0x17;
+        expected[6] = 0x23;
+        assert_eq!(chip8.register, expected);
+    }
 
-        let x_coord = (start_x % 32) as usize;
-        let y_coord = (start_y % 64) as usize;
+    #[test]
+    fn test_set_invalid_register() {
+        let mut chip8 = Chip8::new();
 
-        let start_pixel = (y_coord * 64) + x_coord;
-        let how_many_ones = chip8.screen.how_many_ones();
+        let result = chip8.set_registers(&[(4, 0x17), (16, 0x23)]);
 
-        assert_eq!(how_many_ones, 14);
-        assert_eq!(chip8.register[0xF], 0);
+        assert_eq!(result, Err(Chip8Error::InvalidRegister(16)));
+        assert_eq!(chip8.register[4], 0);
     }
 
     #[test]
-    fn test_draw_sprite_at_x_y_with_height_n_with_collision() {
-        // 0xDXYN: Draws a sprite at coordinate (VX, VY) that has a width of 8 pixels
-        // and a height of N pixels.
-        let height = 5;
-        let start_x = 10;
-        let start_y = 10;
-
-        // Draw the 0 pixel at (10, 10), twice, which should result in
-        // 0 pixels being set to 1, and the `chip8.register[0xF]` should be set to 1,
-        // indicating a collistion
-        let program: Vec<u8> = vec![0xD4, 0x65, 0xD4, 0x65];
+    fn test_tall_sprite_at_the_bottom_edge() {
+        let program: Vec<u8> = vec![
+            0x60, 0x00,
+            0x61, 0x1E, // V1 = 30
+            0xA2, 0x0A,
+            0xD0, 0x1F, // 15 rows
+            0x00, 0x00,
+            0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80,
+        ];
 
-        let mut chip8 = create_and_load(&program).unwrap();
+        let mut clipped = create_and_load(&program).unwrap();
+        clipped.run();
 
-        let how_many_ones = chip8.screen.how_many_ones();
+        assert_eq!(clipped.status(), ExecStatus::Halted);
+        assert_eq!(clipped.screen().how_many_ones(), 2);
 
-        assert_eq!(how_many_ones, 0);
-        assert_eq!(chip8.register[0xF], 0);
+        let mut wrapped = create_and_load(&program).unwrap();
+        wrapped.set_quirks(Quirks { clip_sprites: false, ..Quirks::default() });
+        wrapped.run();
 
-        // set i to the first sprite in the font set (the number 0)
-        chip8.i = 0;
-        chip8.register[4] = start_x;
-        chip8.register[6] = start_y;
+        assert_eq!(wrapped.status(), ExecStatus::Halted);
+        assert_eq!(wrapped.screen().how_many_ones(), 15);
+        assert!((0..13).all(|y| wrapped.screen().get_pixel_color(0, y) == 1));
+    }
 
-        // This will draw the `0` in the first step, and overwrite said zero at the second step.
-        chip8.run();
+    #[test]
+    fn test_call_stack() {
+        let program: Vec<u8> = vec![0x22, 0x04, 0x00, 0x00, 0x60, 0x01];
 
-        let x_coord = (start_x % 32) as usize;
-        let y_coord = (start_y % 64) as usize;
+        let mut chip8 = create_and_load(&program).unwrap();
 
-        let start_pixel = (y_coord * 64) + x_coord;
-        let end_pixel = start_pixel + (64 * height);
+        assert!(chip8.call_stack().is_empty());
 
-        let how_many_ones = chip8.screen.how_many_ones();
+        chip8.step();
 
-        assert_eq!(how_many_ones, 0);
-        assert_eq!(chip8.register[0xF], 1);
+        assert_eq!(chip8.call_stack(), &[LOWER_MEMORY_BOUNDARY]);
     }
 
     #[test]
-    fn test_skip_next_instruction_if_key_in_vx_is_pressed_positive() {
-        // 0xEX9E: Skips the next instruction if the key stored in VX is pressed.
-        let key_index: u8 = 0x4;
-        let program: Vec<u8> = vec![0xE4, 0x9E];
+    fn test_set_call_stack() {
+        let program: Vec<u8> = vec![0x00, 0xEE];
 
         let mut chip8 = create_and_load(&program).unwrap();
 
-        let orig_pc = chip8.pc;
+        assert_eq!(chip8.set_call_stack(&[0x300; STACK + 1]), Err(Chip8Error::StackOverflow));
 
-        chip8.register[4] = key_index;
-        chip8.keyboard.keypress(key_index);
+        chip8.set_call_stack(&[0x300, 0x400]).unwrap();
+        chip8.step();
 
-        chip8.execute_single_instruction();
+        assert_eq!(chip8.pc, 0x402);
+        assert_eq!(chip8.call_stack(), &[0x300]);
+    }
 
-        assert_eq!(chip8.pc, orig_pc + 4);
+    #[test]
+    fn test_platform_clock_speed() {
+        let chip8 = Chip8::builder().platform(Platform::SuperChip).build();
+
+        assert_eq!(chip8.clock_hz(), Platform::SuperChip.default_clock_hz());
+        assert_eq!(chip8.quirks(), Quirks::super_chip());
+        assert_eq!(Chip8::new_for_rom(&[], Platform::CosmacVip).unwrap().clock_hz(), 540);
+        assert_eq!(Chip8::builder().clock_hz(2000).platform(Platform::SuperChip).build().clock_hz(), 2000);
     }
 
     #[test]
-    fn test_skip_next_instruction_if_key_in_vx_is_pressed_negative() {
-        // 0xEX9E: Skips the next instruction if the key stored in VX is pressed.
-        let key_index: u8 = 0x4;
-        let program: Vec<u8> = vec![0xE4, 0x9E];
+    fn test_strict_rejects_skip_with_low_nibble() {
+        let program: Vec<u8> = vec![0x50, 0x11];
 
         let mut chip8 = create_and_load(&program).unwrap();
-        let keys_pressed = chip8.keyboard.any_key_pressed();
 
-        assert_eq!(keys_pressed, false);
+        assert_eq!(chip8.step(), ExecStatus::Error(Chip8Error::UnknownOpcode(0x5011)));
+    }
 
-        let orig_pc = chip8.pc;
+    #[test]
+    fn test_lenient_ignores_skip_low_nibble() {
+        let program: Vec<u8> = vec![0x50, 0x11, 0x00, 0x00, 0x90, 0x11];
 
-        chip8.register[4] = key_index;
+        let mut chip8 = Chip8::builder().strict(false).build();
+        chip8.load_into_memory(program).unwrap();
 
-        let keys_pressed = chip8.keyboard.any_key_pressed();
-        assert_eq!(keys_pressed, false);
+        // V0 == V1, so the 0000 gets skipped.
+        assert_eq!(chip8.step(), ExecStatus::Running);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 4);
+        assert_eq!(chip8.step(), ExecStatus::Running);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 6);
+    }
 
-        chip8.keyboard.keypress(key_index);
+    #[test]
+    fn test_load_rom_from_const() {
+        const ROM: &[u8] = &[0x60, 0x05, 0x70, 0x01];
 
-        chip8.execute_single_instruction();
+        let mut chip8 = Chip8::new();
+        chip8.load_rom(ROM).unwrap();
+        chip8.load_into_memory(ROM).unwrap();
+        chip8.run();
 
-        assert_eq!(chip8.pc, orig_pc + 4);
+        assert_eq!(chip8.register[0], 0x06);
+        assert_eq!(chip8.rom_checksum(), crate::rom::analyze_rom(ROM).checksum);
     }
 
     #[test]
-    fn test_skip_next_instruction_if_key_in_vx_is_not_pressed_positive() {
-        // 0xEXA1: Skips the next instruction if the key stored in VX isn't pressed.
-        let key_index: u8 = 0x4;
-        let program: Vec<u8> = vec![0xE4, 0xA1];
+    fn test_drew_in_frame() {
+        let program: Vec<u8> = vec![
+            0xA0, 0x00,
+            0xD0, 0x01,
+            0x12, 0x04, // loop forever
+        ];
 
         let mut chip8 = create_and_load(&program).unwrap();
-        let keys_pressed = chip8.keyboard.any_key_pressed();
+        chip8.set_quirks(Quirks { display_wait: true, ..Quirks::default() });
 
-        assert_eq!(keys_pressed, false);
+        chip8.run_frame(10);
 
-        let orig_pc = chip8.pc;
-
-        chip8.register[4] = key_index;
-
-        chip8.run();
+        assert!(chip8.drew_in_frame());
+        assert_eq!(chip8.cycles(), 2);
 
-        let keys_pressed = chip8.keyboard.any_key_pressed();
+        chip8.run_frame(10);
 
-        assert_eq!(keys_pressed, false);
-        assert_eq!(chip8.pc, orig_pc + 4);
+        assert!(!chip8.drew_in_frame());
     }
 
     #[test]
-    fn test_skip_next_instruction_if_key_in_vx_is_not_pressed_negative() {
-        // 0xEXA1: Skips the next instruction if the key stored in VX isn't pressed.
-        let key_index: u8 = 0x4;
-        let program: Vec<u8> = vec![0xE4, 0xA1];
+    fn test_beeped_this_frame() {
+        let program: Vec<u8> = vec![
+            0x60, 0x01,
+            0xF0, 0x18,
+            0x60, 0x00,
+            0xF0, 0x18, // stopped again within the same frame
+            0x12, 0x08, // loop forever
+        ];
 
         let mut chip8 = create_and_load(&program).unwrap();
-        let keys_pressed = chip8.keyboard.any_key_pressed();
+        chip8.run_frame(10);
 
-        assert_eq!(keys_pressed, false);
-
-        let orig_pc = chip8.pc;
+        assert!(chip8.beeped_this_frame());
+        assert_eq!(chip8.sound_timer, 0);
 
-        chip8.register[4] = key_index;
-        chip8.keyboard.keypress(key_index);
+        chip8.run_frame(10);
 
-        chip8.execute_single_instruction();
+        assert!(!chip8.beeped_this_frame());
 
-        let keys_pressed = chip8.keyboard.any_key_pressed();
+        chip8.sound_timer = 1;
+        chip8.run_frame(10);
 
-        assert_eq!(keys_pressed, true);
-        assert_eq!(chip8.pc, orig_pc + 2);
+        assert!(chip8.beeped_this_frame());
     }
 
     #[test]
-    fn test_set_vx_to_value_of_delay_timer() {
-        // 0xFX07: Sets VX to the value of the delay timer.
-        let test_value: u8 = 23;
-        let program: Vec<u8> = vec![0xF4, 0x07];
+    fn test_current_instruction_text() {
+        let program: Vec<u8> = vec![0x64, 0xAA, 0x50, 0x01];
 
         let mut chip8 = create_and_load(&program).unwrap();
 
-        assert_eq!(chip8.delay_timer, 0);
+        assert_eq!(chip8.current_instruction_text(), "LD V4, 0xAA");
 
-        chip8.delay_timer = test_value;
+        chip8.step();
 
-        chip8.run();
+        assert_eq!(chip8.current_instruction_text(), "DB 0x5001");
 
-        assert_eq!(chip8.register[4], test_value);
+        chip8.load_into_memory(vec![0xF0, 0x00, 0x12, 0x34]).unwrap();
+        chip8.set_pc(LOWER_MEMORY_BOUNDARY);
+
+        assert_eq!(chip8.current_instruction_text(), "LD I, 0x1234");
     }
 
     #[test]
-    fn test_wait_for_keypress() {
-        // 0xFX0A: A key press is awaited, and then stored in VX.
-        // (Blocking Operation. All instruction halted until next key event)
-        let key_index: u8 = 0x4;
-        let program: Vec<u8> = vec![
-            0xF4, 0x0A
-        ];
+    fn test_advance() {
+        let program: Vec<u8> = vec![0x70, 0x01, 0x12, 0x00];
 
         let mut chip8 = create_and_load(&program).unwrap();
+        chip8.delay_timer = 10;
+        chip8.advance(Duration::from_millis(50));
 
-        assert_eq!(chip8.register[4], 0);
-        let orig_pc = chip8.pc;
+        assert_eq!(chip8.cycles(), 30);
+        assert_eq!(chip8.delay_timer, 7);
 
-        // After this, everything should be just as it was,
-        // since no key has been pressed and program counter wasn't incremented
-        chip8.execute_single_instruction();
+        // neither call alone is a whole frame, together they are.
+        chip8.advance(Duration::from_millis(10));
+        chip8.advance(Duration::from_millis(7));
 
-        assert_eq!(chip8.pc, orig_pc);
+        assert_eq!(chip8.cycles(), 40);
+        assert_eq!(chip8.delay_timer, 6);
+    }
 
-        // Now set the key, and go again
-        chip8.keyboard.keypress(key_index);
+    #[test]
+    fn test_advance_caps_instructions_but_not_timers() {
+        let program: Vec<u8> = vec![0x70, 0x01, 0x12, 0x00];
 
-        // After this time, the key index should be in `chip8.register[4]`,
-        // the `chip8.key` array should be all `0`, and `self.pc` should have been advanced
-        chip8.execute_single_instruction();
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.delay_timer = 200;
+        chip8.advance(Duration::from_secs(10));
 
-        assert_eq!(chip8.register[4], key_index);
-        assert_eq!(chip8.pc, orig_pc + 2);
+        assert_eq!(chip8.cycles(), 150);
+        assert_eq!(chip8.delay_timer, 0);
     }
 
     #[test]
-    fn test_set_delay_timer_to_vx() {
-        // 0xFX15: Sets the delay timer to VX.
-        let program: Vec<u8> = vec![0xF4, 0x15];
+    fn test_advance_with_a_very_slow_clock() {
+        let program: Vec<u8> = vec![0x70, 0x01, 0x12, 0x00];
 
         let mut chip8 = create_and_load(&program).unwrap();
+        chip8.set_clock_hz(2);
+        chip8.advance(Duration::from_secs(10));
 
-        assert_eq!(chip8.delay_timer, 0);
+        assert_eq!(chip8.cycles(), 1);
 
-        chip8.register[4] = 0x17;
+        chip8.advance(Duration::from_millis(400));
 
-        chip8.run();
+        assert_eq!(chip8.cycles(), 1);
 
-        // the value is 1 less than what was set, because
-        // the `process_timers` method has been called
-        assert_eq!(chip8.delay_timer, 0x17 - 1);
+        chip8.advance(Duration::from_millis(100));
+
+        assert_eq!(chip8.cycles(), 2);
     }
 
     #[test]
-    fn test_set_sound_timer_to_vx() {
-        // 0xFX18: Sets the delay timer to VX.
-        let program: Vec<u8> = vec![0xF4, 0x18];
+    fn test_keys_are_read_from_the_frame_snapshot() {
+        struct SharedKeys(Arc<core::sync::atomic::AtomicU16>);
+
+        impl InputSource for SharedKeys {
+            fn is_pressed(&self, key: u8) -> bool {
+                self.0.load(Ordering::SeqCst) & (1 << key) != 0
+            }
+
+            fn wait_key(&mut self) -> Option<u8> {
+                None
+            }
+        }
+
+        let program: Vec<u8> = vec![
+            0x01, 0x23, // the hook presses key 5 in the middle of the frame
+            0x60, 0x05,
+            0xE0, 0x9E,
+            0x61, 0x01,
+            0x12, 0x08, // loop forever
+        ];
+        let keys = Arc::new(core::sync::atomic::AtomicU16::new(0));
 
         let mut chip8 = create_and_load(&program).unwrap();
+        chip8.set_input_source(Box::new(SharedKeys(Arc::clone(&keys))));
+        let hook_keys = Arc::clone(&keys);
+        chip8.set_sys_hook(Box::new(move |_| hook_keys.store(1 << 5, Ordering::SeqCst)));
 
-        assert_eq!(chip8.sound_timer, 0);
+        chip8.run_frame(10);
 
-        chip8.register[4] = 0x17;
+        assert_eq!(chip8.register[1], 1);
+        assert_eq!(chip8.keys_snapshot(), 1 << 5);
 
-        chip8.run();
+        chip8.register[1] = 0;
+        chip8.set_pc(LOWER_MEMORY_BOUNDARY + 4);
+        chip8.run_frame(10);
 
-        // the value is 1 less than what was set, because
-        // the `process_timers` method has been called
-        assert_eq!(chip8.sound_timer, 0x17 - 1);
+        assert_eq!(chip8.register[1], 0);
     }
 
     #[test]
-    fn test_add_vx_to_i_with_no_overflow() {
-        // 0xFX1E: Adds VX to I. VF is set to 1 when there is a range overflow (I+VX>0xFFF),
-        // and to 0 when there isn't.
-        let program: Vec<u8> = vec![0xF4, 0x1E];
+    fn test_set_clock_hz() {
+        let program: Vec<u8> = vec![0x12, 0x00]; // loop forever
 
         let mut chip8 = create_and_load(&program).unwrap();
+        chip8.set_clock_hz(600);
+        chip8.run_clocked_frame();
 
-        chip8.i = 0xA;
-        chip8.register[4] = 0x17;
+        assert_eq!(chip8.clock_hz(), 600);
+        assert_eq!(chip8.cycles(), 10);
 
-        chip8.run();
+        chip8.set_clock_hz(1200);
+        chip8.run_clocked_frame();
 
-        assert_eq!(chip8.i, 0x21);
-        assert_eq!(chip8.register[0xF], 0);
+        assert_eq!(chip8.clock_hz(), 1200);
+        assert_eq!(chip8.cycles(), 30);
+
+        chip8.advance(Duration::from_millis(100));
+
+        assert_eq!(chip8.cycles(), 150);
     }
 
     #[test]
-    fn test_add_vx_to_i_with_overflow() {
-        // 0xFX1E: Adds VX to I. VF is set to 1 when there is a range overflow (I+VX>0xFFF),
-        // and to 0 when there isn't.
-        let program: Vec<u8> = vec![0xF4, 0x1E];
+    #[cfg(debug_assertions)]
+    fn test_low_sprite_warning() {
+        let program: Vec<u8> = vec![
+            0xA0, 0x00,
+            0xD0, 0x05, // I was left at 0
+            0xA2, 0x00,
+            0xD0, 0x05,
+        ];
+        let warnings = Arc::new(std::sync::Mutex::new(Vec::new()));
 
         let mut chip8 = create_and_load(&program).unwrap();
+        let hook_warnings = Arc::clone(&warnings);
+        chip8.set_low_sprite_warning(Box::new(move |i| hook_warnings.lock().unwrap().push(i)));
+        for _ in 0..4 {
+            chip8.step();
+        }
 
-        chip8.i = 0xFFA;
-        chip8.register[4] = 0xA;
-
-        chip8.run();
-
-        assert_eq!(chip8.i, 0x1004);
-        assert_eq!(chip8.register[0xF], 1);
+        assert_eq!(*warnings.lock().unwrap(), vec![0x000]);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 8);
     }
 
     #[test]
-    fn test_set_i_to_location_of_sprite_for_character_in_vx() {
-        // 0xFX29: Sets I to the location of the sprite for the character in VX.
-        // Characters 0-F (in hexadecimal) are represented by a 4x5 font.
-        let program: Vec<u8> = vec![0xF4, 0x29];
+    fn test_load_hex_string() {
+        let mut chip8 = Chip8::new();
+        chip8.load_hex_string("64 AA").unwrap();
+        chip8.step();
 
-        let mut chip8 = create_and_load(&program).unwrap();
+        assert_eq!(chip8.register[4], 0xAA);
 
-        chip8.register[4] = 2;
+        chip8.load_hex_string("600A,D015\n\t7001").unwrap();
+
+        let loaded: Vec<u8> = (0..6).map(|idx| chip8.memory[LOWER_MEMORY_BOUNDARY + idx]).collect();
+        assert_eq!(loaded, vec![0x60, 0x0A, 0xD0, 0x15, 0x70, 0x01]);
+        assert_eq!(
+            chip8.load_hex_string("60 0G"),
+            Err(Chip8Error::InvalidHexString { offset: 4, reason: "not a hex digit" })
+        );
+        assert_eq!(
+            chip8.load_hex_string("60 0"),
+            Err(Chip8Error::InvalidHexString { offset: 4, reason: "odd number of hex digits" })
+        );
+    }
 
-        chip8.run();
+    #[test]
+    fn test_trace_run() {
+        let program: Vec<u8> = vec![
+            0x60, 0x02,
+            0x70, 0xFF,
+            0x30, 0x00,
+            0x12, 0x02, // until V0 reaches 0
+            0x81, 0x06,
+        ];
 
-        assert_eq!(chip8.i, 10);
+        let mut first = create_and_load(&program).unwrap();
+        let mut second = create_and_load(&program).unwrap();
+        let first_trace = first.trace_run(100);
+
+        assert_eq!(first_trace, second.trace_run(100));
+        assert_eq!(
+            first_trace,
+            vec![
+                (0x200, 0x6002), (0x202, 0x70FF), (0x204, 0x3000), (0x206, 0x1202),
+                (0x202, 0x70FF), (0x204, 0x3000), (0x208, 0x8106),
+            ]
+        );
+        assert_eq!(first.status(), ExecStatus::Halted);
+
+        // the shift quirk only changes what 8XY6 computes, not the path through the program.
+        let mut shifted = create_and_load(&program).unwrap();
+        shifted.set_quirks(Quirks { shift_uses_vy: !Quirks::default().shift_uses_vy, ..Quirks::default() });
+
+        assert_eq!(shifted.trace_run(100), first_trace);
+        assert_eq!(first.trace_run(100), vec![]);
     }
 
     #[test]
-    fn test_store_binary_coded_decimal_representation_of_vx() {
-        // 0xFX33: Stores the binary-coded decimal representation of VX, with the most
-        // significant of three digits at the address in I, the middle digit at I plus 1,
-        // and the least significant digit at I plus 2.
-        let program: Vec<u8> = vec![0xF4, 0x33];
+    fn test_reload_program() {
+        let program_a: Vec<u8> = vec![
+            0x60, 0x05,
+            0xD1, 0x15, // the "0" glyph
+            0x61, 0x0A,
+            0x62, 0x0B,
+        ];
+        let program_b: Vec<u8> = vec![0x70, 0x01, 0x81, 0x04];
 
-        let mut chip8 = create_and_load(&program).unwrap();
+        let mut chip8 = create_and_load(&program_a).unwrap();
+        chip8.run();
+        let lit = chip8.screen.how_many_ones();
 
-        let first_i = LOWER_MEMORY_BOUNDARY;
-        chip8.i = first_i;
-        chip8.register[4] = 0xDC;
+        chip8.reload_program(&program_b).unwrap();
+
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY);
+        assert_eq!(chip8.memory[LOWER_MEMORY_BOUNDARY + 4], 0x0);
+        assert_eq!(chip8.memory[FONTSET_START], CHIP8_FONTSET[0]);
 
         chip8.run();
 
-        assert_eq!(chip8.memory[first_i], 2);
-        assert_eq!(chip8.memory[first_i + 1], 2);
-        assert_eq!(chip8.memory[first_i + 2], 0);
+        assert_eq!(chip8.register[0], 6);
+        assert_eq!(chip8.register[1], 0x10);
+        assert_eq!(chip8.register[2], 0x0B);
+        assert_eq!(chip8.screen.how_many_ones(), lit);
+        assert_eq!(chip8.status(), ExecStatus::Halted);
+
+        assert!(chip8.reload_program(&vec![0; chip8.memory.size()]).is_err());
+        assert_eq!(chip8.memory[LOWER_MEMORY_BOUNDARY], 0x70);
     }
 
     #[test]
-    fn test_store_v0_to_vx_in_memory_starting_at_address_i() {
-        // 0xFX55: Stores V0 to VX (including VX) in memory starting at address I.
-        // The offset from I is increased by 1 for each value written, but I itself
-        // is left unmodified.
+    fn test_reload_program_drops_history() {
+        let mut chip8 = create_and_load(&[0x60, 0x05, 0x61, 0x0A]).unwrap();
+        chip8.enable_history(8);
+        chip8.watch_register(0);
+        chip8.step();
 
-        let program: Vec<u8> = vec![
-            0xF4, 0x55,
-            0x0, 0x0 // exit
-        ];
+        assert_eq!(chip8.watch_hit(), Some(0));
 
-        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.reload_program(&[0x70, 0x01]).unwrap();
 
-        let first_i = LOWER_MEMORY_BOUNDARY + 4;
-        chip8.i = first_i;
+        assert_eq!(chip8.watch_hit(), None);
+        assert!(!chip8.step_back());
+        assert_eq!(chip8.memory[LOWER_MEMORY_BOUNDARY], 0x70);
+        assert_eq!(chip8.register[0], 5);
+    }
 
-        for i in 0..5 {
-            chip8.register[i as usize] = i + 1;
+    #[test]
+    fn test_wait_for_key_sees_a_mid_frame_press_in_the_next_frame() {
+        // a keypad reporting presses once, like `Keypad` does.
+        struct SharedKeys {
+            held: Arc<core::sync::atomic::AtomicU16>,
+            pressed: Arc<std::sync::Mutex<Option<u8>>>,
         }
 
-        chip8.run();
+        impl InputSource for SharedKeys {
+            fn is_pressed(&self, key: u8) -> bool {
+                self.held.load(Ordering::SeqCst) & (1 << key) != 0
+            }
 
-        for i in 0..5 {
-            assert_eq!(chip8.memory[first_i + i], (i + 1) as u8);
+            fn wait_key(&mut self) -> Option<u8> {
+                self.pressed.lock().unwrap().take()
+            }
         }
-    }
 
-    #[test]
-    fn test_fill_v0_to_vx_with_values_from_memory_starting_at_address_i() {
-        // 0xFX65: Fills V0 to VX (including VX) with values from memory
-        // starting at address I. The offset from I is increased by 1 for
-        // each value written, but I itself is left unmodified.
         let program: Vec<u8> = vec![
-            0xF4, 0x65,
-            0x0, 0x0 // exit
+            0x01, 0x23, // the hook presses and holds key 7 in the middle of the frame
+            0xF3, 0x0A,
+            0x12, 0x04, // loop forever
         ];
+        let held = Arc::new(core::sync::atomic::AtomicU16::new(0));
+        let pressed = Arc::new(std::sync::Mutex::new(None));
 
         let mut chip8 = create_and_load(&program).unwrap();
+        chip8.set_input_source(Box::new(SharedKeys { held: Arc::clone(&held), pressed: Arc::clone(&pressed) }));
+        chip8.set_sys_hook(Box::new(move |_| {
+            held.store(1 << 7, Ordering::SeqCst);
+            *pressed.lock().unwrap() = Some(7);
+        }));
 
-        let first_i = (LOWER_MEMORY_BOUNDARY + 4);
-        chip8.i = first_i;
+        chip8.run_frame(10);
 
-        for i in 0..5 {
-            chip8.memory[first_i + i] = i as u8 + 1;
-        }
+        assert_eq!(chip8.status(), ExecStatus::WaitingForKey);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 2);
 
-        chip8.run();
+        chip8.run_frame(10);
 
-        for i in 0..5 {
-            assert_eq!(chip8.register[i as usize], i + 1);
-        }
+        assert_eq!(chip8.register[3], 7);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 4);
+
+        // a frame starting with key 2 held gets key 2, not the one pressed during the frame
+        let held = Arc::new(core::sync::atomic::AtomicU16::new(1 << 2));
+        let pressed = Arc::new(std::sync::Mutex::new(None));
+
+        let mut chip8 = create_and_load(&program).unwrap();
+        chip8.set_input_source(Box::new(SharedKeys { held: Arc::clone(&held), pressed: Arc::clone(&pressed) }));
+        chip8.set_sys_hook(Box::new(move |_| {
+            held.store(1 << 2 | 1 << 7, Ordering::SeqCst);
+            *pressed.lock().unwrap() = Some(7);
+        }));
+
+        chip8.run_frame(10);
+
+        assert_eq!(chip8.register[3], 2);
+        assert_eq!(chip8.pc, LOWER_MEMORY_BOUNDARY + 4);
     }
 
-    fn create_and_load(program: &Vec<u8>) -> Result<Chip8, Box<dyn Error>> {
+    fn create_and_load(program: &[u8]) -> Result<Chip8, Chip8Error> {
         let mut chip8 = Chip8::new();
 
-        chip8.load_into_memory(program.clone())?;
+        chip8.load_into_memory(program)?;
 
         Ok(chip8)
     }
@@ -0,0 +1,152 @@
+// Mnemonics follow Cowgod's Chip-8 technical reference, e.g. `LD V4, 0xAA` or `DRW V4, V6, 5`.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use crate::instruction::{decode, Instruction};
+
+pub fn disassemble(program: &[u8]) -> Vec<String> {
+    program
+        .chunks(2)
+        .map(|word| {
+            let high_byte = word[0] as u16;
+            let low_byte = word.get(1).copied().unwrap_or(0) as u16;
+            disassemble_opcode(high_byte << 8 | low_byte)
+        })
+        .collect()
+}
+
+pub fn disassemble_opcode(opcode: u16) -> String {
+    let Some(instruction) = decode(opcode) else {
+        // not a known instruction, most likely data.
+        return format!("DB 0x{opcode:04X}");
+    };
+
+    match instruction {
+        Instruction::Cls => "CLS".to_string(),
+        Instruction::Ret => "RET".to_string(),
+        Instruction::Sys { nnn } => format!("SYS 0x{nnn:03X}"),
+        Instruction::Jp { nnn } => format!("JP 0x{nnn:03X}"),
+        Instruction::Call { nnn } => format!("CALL 0x{nnn:03X}"),
+        Instruction::SeByte { x, nn } => format!("SE V{x:X}, 0x{nn:02X}"),
+        Instruction::SneByte { x, nn } => format!("SNE V{x:X}, 0x{nn:02X}"),
+        Instruction::Se { x, y } => format!("SE V{x:X}, V{y:X}"),
+        Instruction::LdByte { x, nn } => format!("LD V{x:X}, 0x{nn:02X}"),
+        Instruction::AddByte { x, nn } => format!("ADD V{x:X}, 0x{nn:02X}"),
+        Instruction::Ld { x, y } => format!("LD V{x:X}, V{y:X}"),
+        Instruction::Or { x, y } => format!("OR V{x:X}, V{y:X}"),
+        Instruction::And { x, y } => format!("AND V{x:X}, V{y:X}"),
+        Instruction::Xor { x, y } => format!("XOR V{x:X}, V{y:X}"),
+        Instruction::Add { x, y } => format!("ADD V{x:X}, V{y:X}"),
+        Instruction::Sub { x, y } => format!("SUB V{x:X}, V{y:X}"),
+        Instruction::Shr { x, y } => format!("SHR V{x:X}, V{y:X}"),
+        Instruction::Subn { x, y } => format!("SUBN V{x:X}, V{y:X}"),
+        Instruction::Shl { x, y } => format!("SHL V{x:X}, V{y:X}"),
+        Instruction::Sne { x, y } => format!("SNE V{x:X}, V{y:X}"),
+        Instruction::LdI { nnn } => format!("LD I, 0x{nnn:03X}"),
+        Instruction::JpV0 { nnn } => format!("JP V0, 0x{nnn:03X}"),
+        Instruction::Rnd { x, nn } => format!("RND V{x:X}, 0x{nn:02X}"),
+        Instruction::Drw { x, y, n } => format!("DRW V{x:X}, V{y:X}, {n}"),
+        Instruction::Skp { x } => format!("SKP V{x:X}"),
+        Instruction::Sknp { x } => format!("SKNP V{x:X}"),
+        // XO-CHIP, the address is the word following the opcode.
+        Instruction::LdILong => "LD I, long".to_string(),
+        Instruction::LdVxDt { x } => format!("LD V{x:X}, DT"),
+        Instruction::LdVxK { x } => format!("LD V{x:X}, K"),
+        Instruction::LdDtVx { x } => format!("LD DT, V{x:X}"),
+        Instruction::LdStVx { x } => format!("LD ST, V{x:X}"),
+        Instruction::AddI { x } => format!("ADD I, V{x:X}"),
+        Instruction::LdF { x } => format!("LD F, V{x:X}"),
+        Instruction::LdHf { x } => format!("LD HF, V{x:X}"),
+        Instruction::LdB { x } => format!("LD B, V{x:X}"),
+        Instruction::LdIVx { x } => format!("LD [I], V{x:X}"),
+        Instruction::LdVxI { x } => format!("LD V{x:X}, [I]"),
+    }
+}
+
+// The instruction form an opcode belongs to, e.g. `ADD Vx, Vy` for 0x8454.
+pub fn instruction_kind(opcode: u16) -> Option<&'static str> {
+    let kind = match decode(opcode)? {
+        Instruction::Cls => "CLS",
+        Instruction::Ret => "RET",
+        Instruction::Sys { .. } => "SYS addr",
+        Instruction::Jp { .. } => "JP addr",
+        Instruction::Call { .. } => "CALL addr",
+        Instruction::SeByte { .. } => "SE Vx, byte",
+        Instruction::SneByte { .. } => "SNE Vx, byte",
+        Instruction::Se { .. } => "SE Vx, Vy",
+        Instruction::LdByte { .. } => "LD Vx, byte",
+        Instruction::AddByte { .. } => "ADD Vx, byte",
+        Instruction::Ld { .. } => "LD Vx, Vy",
+        Instruction::Or { .. } => "OR Vx, Vy",
+        Instruction::And { .. } => "AND Vx, Vy",
+        Instruction::Xor { .. } => "XOR Vx, Vy",
+        Instruction::Add { .. } => "ADD Vx, Vy",
+        Instruction::Sub { .. } => "SUB Vx, Vy",
+        Instruction::Shr { .. } => "SHR Vx, Vy",
+        Instruction::Subn { .. } => "SUBN Vx, Vy",
+        Instruction::Shl { .. } => "SHL Vx, Vy",
+        Instruction::Sne { .. } => "SNE Vx, Vy",
+        Instruction::LdI { .. } => "LD I, addr",
+        Instruction::JpV0 { .. } => "JP V0, addr",
+        Instruction::Rnd { .. } => "RND Vx, byte",
+        Instruction::Drw { .. } => "DRW Vx, Vy, nibble",
+        Instruction::Skp { .. } => "SKP Vx",
+        Instruction::Sknp { .. } => "SKNP Vx",
+        Instruction::LdILong => "LD I, long",
+        Instruction::LdVxDt { .. } => "LD Vx, DT",
+        Instruction::LdVxK { .. } => "LD Vx, K",
+        Instruction::LdDtVx { .. } => "LD DT, Vx",
+        Instruction::LdStVx { .. } => "LD ST, Vx",
+        Instruction::AddI { .. } => "ADD I, Vx",
+        Instruction::LdF { .. } => "LD F, Vx",
+        Instruction::LdHf { .. } => "LD HF, Vx",
+        Instruction::LdB { .. } => "LD B, Vx",
+        Instruction::LdIVx { .. } => "LD [I], Vx",
+        Instruction::LdVxI { .. } => "LD Vx, [I]",
+    };
+    Some(kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disassemble_program() {
+        let program: Vec<u8> = vec![0x00, 0xE0, 0x64, 0xAA, 0x84, 0x54, 0xD4, 0x65];
+
+        let mnemonics = disassemble(&program);
+
+        assert_eq!(mnemonics, vec!["CLS", "LD V4, 0xAA", "ADD V4, V5", "DRW V4, V6, 5"]);
+    }
+
+    #[test]
+    fn test_instruction_kind() {
+        assert_eq!(instruction_kind(0x8454), Some("ADD Vx, Vy"));
+        assert_eq!(instruction_kind(0x7401), Some("ADD Vx, byte"));
+        assert_eq!(instruction_kind(0xF41E), Some("ADD I, Vx"));
+        assert_eq!(instruction_kind(0x5001), None);
+    }
+
+    #[test]
+    fn test_disassemble_long_load() {
+        assert_eq!(disassemble_opcode(0xF000), "LD I, long");
+        assert_eq!(disassemble_opcode(0xF100), "DB 0xF100");
+    }
+
+    #[test]
+    fn test_every_instruction_has_a_mnemonic_and_kind() {
+        for opcode in 0..=u16::MAX {
+            let known = decode(opcode).is_some();
+
+            assert_eq!(instruction_kind(opcode).is_some(), known, "{opcode:#06X}");
+            assert_eq!(disassemble_opcode(opcode).starts_with("DB "), !known, "{opcode:#06X}");
+        }
+    }
+
+    #[test]
+    fn test_disassemble_unknown_opcode() {
+        assert_eq!(disassemble_opcode(0x5001), "DB 0x5001");
+    }
+}
@@ -0,0 +1,55 @@
+use core::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Chip8Error {
+    // the program doesn't fit between its load address and the end of memory.
+    ProgramTooLarge { program_len: usize, available: usize },
+    // `len` bytes written at `addr` would not fit into memory.
+    AddressOutOfBounds { addr: u16, len: usize },
+    UnknownOpcode(u16),
+    // there are only the registers V0 to VF.
+    InvalidRegister(usize),
+    // a 2NNN with all stack levels in use.
+    StackOverflow,
+    // a 00EE without a matching call.
+    StackUnderflow,
+    // a 00EE popping an address the next instruction can't be read from, i.e. a corrupted stack.
+    InvalidReturnAddress(u16),
+    // a font given to `Chip8::set_font` that isn't 16 glyphs of 5 bytes.
+    InvalidFontLength(usize),
+    // FX30 with a digit the SUPER-CHIP big font has no glyph for, i.e. above 9.
+    NoBigFontGlyph(u8),
+    // a malformed line in an Intel HEX file.
+    InvalidIntelHex { line: usize, reason: &'static str },
+    // `load_hex_string` input with something other than hex digits, whitespace and commas at
+    // byte `offset`, or an odd number of digits.
+    InvalidHexString { offset: usize, reason: &'static str },
+    // two segments given to `load_segments` share memory, `addr` is where the later one starts.
+    OverlappingSegments { addr: u16 },
+}
+
+impl Display for Chip8Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Chip8Error::ProgramTooLarge { program_len, available } => {
+                write!(f, "Program is {program_len} bytes but only {available} bytes are available.")
+            }
+            Chip8Error::AddressOutOfBounds { addr, len } => {
+                write!(f, "{len} bytes at {addr:#05X} do not fit into memory.")
+            }
+            Chip8Error::UnknownOpcode(opcode) => write!(f, "Unknown opcode {opcode:#06X}."),
+            Chip8Error::InvalidRegister(index) => write!(f, "There is no register V{index:X}."),
+            Chip8Error::StackOverflow => write!(f, "Stack overflow."),
+            Chip8Error::StackUnderflow => write!(f, "Stack underflow."),
+            Chip8Error::InvalidReturnAddress(addr) => write!(f, "Return address {addr:#05X} is outside of memory."),
+            Chip8Error::InvalidFontLength(len) => write!(f, "A font must be 80 bytes, not {len}."),
+            Chip8Error::NoBigFontGlyph(digit) => write!(f, "There is no big font glyph for {digit:X}."),
+            Chip8Error::InvalidIntelHex { line, reason } => write!(f, "Invalid Intel HEX on line {line}: {reason}."),
+            Chip8Error::InvalidHexString { offset, reason } => write!(f, "Invalid hex string at offset {offset}: {reason}."),
+            Chip8Error::OverlappingSegments { addr } => write!(f, "Segment at {addr:#05X} overlaps another one."),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Chip8Error {}
@@ -0,0 +1,90 @@
+// Intel HEX, as emitted by some CHIP-8 toolchains instead of raw binaries.
+// Only data (00) and end of file (01) records are supported, which is all a 16-bit address space needs.
+
+use alloc::vec::Vec;
+use crate::error::Chip8Error;
+
+const DATA_RECORD: u8 = 0x00;
+const END_OF_FILE_RECORD: u8 = 0x01;
+
+// Returns the (address, bytes) of every data record up to the end of file record.
+pub(crate) fn parse(hex: &str) -> Result<Vec<(u16, Vec<u8>)>, Chip8Error> {
+    let mut records = Vec::new();
+
+    for (idx, line) in hex.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |reason| Chip8Error::InvalidIntelHex { line: idx + 1, reason };
+
+        let digits = line.strip_prefix(':').ok_or(error("missing start code"))?;
+        if !digits.len().is_multiple_of(2) || !digits.is_ascii() {
+            return Err(error("invalid hex digits"));
+        }
+        let bytes = (0..digits.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| error("invalid hex digits"))?;
+
+        // length, 2 address bytes, record type, data and the checksum
+        if bytes.len() < 5 || bytes.len() != bytes[0] as usize + 5 {
+            return Err(error("record length mismatch"));
+        }
+        if bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0 {
+            return Err(error("bad checksum"));
+        }
+
+        let address = u16::from_be_bytes([bytes[1], bytes[2]]);
+        match bytes[3] {
+            DATA_RECORD => records.push((address, bytes[4..bytes.len() - 1].to_vec())),
+            END_OF_FILE_RECORD => return Ok(records),
+            _ => return Err(error("unsupported record type")),
+        }
+    }
+
+    Err(Chip8Error::InvalidIntelHex { line: hex.lines().count(), reason: "missing end of file record" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let hex = ":040200006005700124\n:02030000F0907B\n:00000001FF\n";
+
+        let records = parse(hex).unwrap();
+
+        assert_eq!(records, vec![(0x200, vec![0x60, 0x05, 0x70, 0x01]), (0x300, vec![0xF0, 0x90])]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse(":040200006005700125\n:00000001FF"),
+            Err(Chip8Error::InvalidIntelHex { line: 1, reason: "bad checksum" })
+        );
+        assert_eq!(
+            parse(":00000001FF\n").map(|records| records.len()),
+            Ok(0)
+        );
+        assert_eq!(
+            parse(":020000040001F9\n:00000001FF"),
+            Err(Chip8Error::InvalidIntelHex { line: 1, reason: "unsupported record type" })
+        );
+        assert_eq!(
+            parse("040200006005700124"),
+            Err(Chip8Error::InvalidIntelHex { line: 1, reason: "missing start code" })
+        );
+        assert_eq!(
+            parse(":0402000060057001"),
+            Err(Chip8Error::InvalidIntelHex { line: 1, reason: "record length mismatch" })
+        );
+        assert_eq!(
+            parse(":040200006005700124"),
+            Err(Chip8Error::InvalidIntelHex { line: 1, reason: "missing end of file record" })
+        );
+    }
+}
@@ -0,0 +1,199 @@
+// Structured form of an opcode. Variant names follow the Cowgod mnemonics used by the disassembler,
+// registers are given by their index (e.g. `x: 4` for V4).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    Cls,
+    Ret,
+    Sys { nnn: u16 },
+    Jp { nnn: u16 },
+    Call { nnn: u16 },
+    SeByte { x: u8, nn: u8 },
+    SneByte { x: u8, nn: u8 },
+    Se { x: u8, y: u8 },
+    LdByte { x: u8, nn: u8 },
+    AddByte { x: u8, nn: u8 },
+    Ld { x: u8, y: u8 },
+    Or { x: u8, y: u8 },
+    And { x: u8, y: u8 },
+    Xor { x: u8, y: u8 },
+    Add { x: u8, y: u8 },
+    Sub { x: u8, y: u8 },
+    Shr { x: u8, y: u8 },
+    Subn { x: u8, y: u8 },
+    Shl { x: u8, y: u8 },
+    Sne { x: u8, y: u8 },
+    LdI { nnn: u16 },
+    JpV0 { nnn: u16 },
+    Rnd { x: u8, nn: u8 },
+    Drw { x: u8, y: u8, n: u8 },
+    Skp { x: u8 },
+    Sknp { x: u8 },
+    // F000 NNNN, the address is the word following the opcode.
+    LdILong,
+    LdVxDt { x: u8 },
+    LdVxK { x: u8 },
+    LdDtVx { x: u8 },
+    LdStVx { x: u8 },
+    AddI { x: u8 },
+    LdF { x: u8 },
+    LdHf { x: u8 },
+    LdB { x: u8 },
+    LdIVx { x: u8 },
+    LdVxI { x: u8 },
+}
+
+// Returns None for words that are not a known instruction, most likely data.
+pub fn decode(opcode: u16) -> Option<Instruction> {
+    let opcode_group = ((opcode & 0xF000) >> 12) as u8;
+    let x = ((opcode & 0x0F00) >> 8) as u8;
+    let y = ((opcode & 0x00F0) >> 4) as u8;
+    let n = (opcode & 0x000F) as u8;
+    let nnn = opcode & 0x0FFF;
+    let nn = (opcode & 0x00FF) as u8;
+
+    let instruction = match (opcode_group, x, y, n) {
+        (0x0, 0x0, 0xE, 0x0) => Instruction::Cls,
+        (0x0, 0x0, 0xE, 0xE) => Instruction::Ret,
+        (0x0, _, _, _) => Instruction::Sys { nnn },
+        (0x1, _, _, _) => Instruction::Jp { nnn },
+        (0x2, _, _, _) => Instruction::Call { nnn },
+        (0x3, _, _, _) => Instruction::SeByte { x, nn },
+        (0x4, _, _, _) => Instruction::SneByte { x, nn },
+        (0x5, _, _, 0x0) => Instruction::Se { x, y },
+        (0x6, _, _, _) => Instruction::LdByte { x, nn },
+        (0x7, _, _, _) => Instruction::AddByte { x, nn },
+        (0x8, _, _, 0x0) => Instruction::Ld { x, y },
+        (0x8, _, _, 0x1) => Instruction::Or { x, y },
+        (0x8, _, _, 0x2) => Instruction::And { x, y },
+        (0x8, _, _, 0x3) => Instruction::Xor { x, y },
+        (0x8, _, _, 0x4) => Instruction::Add { x, y },
+        (0x8, _, _, 0x5) => Instruction::Sub { x, y },
+        (0x8, _, _, 0x6) => Instruction::Shr { x, y },
+        (0x8, _, _, 0x7) => Instruction::Subn { x, y },
+        (0x8, _, _, 0xE) => Instruction::Shl { x, y },
+        (0x9, _, _, 0x0) => Instruction::Sne { x, y },
+        (0xA, _, _, _) => Instruction::LdI { nnn },
+        (0xB, _, _, _) => Instruction::JpV0 { nnn },
+        (0xC, _, _, _) => Instruction::Rnd { x, nn },
+        (0xD, _, _, _) => Instruction::Drw { x, y, n },
+        (0xE, _, 0x9, 0xE) => Instruction::Skp { x },
+        (0xE, _, 0xA, 0x1) => Instruction::Sknp { x },
+        (0xF, 0x0, 0x0, 0x0) => Instruction::LdILong,
+        (0xF, _, 0x0, 0x7) => Instruction::LdVxDt { x },
+        (0xF, _, 0x0, 0xA) => Instruction::LdVxK { x },
+        (0xF, _, 0x1, 0x5) => Instruction::LdDtVx { x },
+        (0xF, _, 0x1, 0x8) => Instruction::LdStVx { x },
+        (0xF, _, 0x1, 0xE) => Instruction::AddI { x },
+        (0xF, _, 0x2, 0x9) => Instruction::LdF { x },
+        (0xF, _, 0x3, 0x0) => Instruction::LdHf { x },
+        (0xF, _, 0x3, 0x3) => Instruction::LdB { x },
+        (0xF, _, 0x5, 0x5) => Instruction::LdIVx { x },
+        (0xF, _, 0x6, 0x5) => Instruction::LdVxI { x },
+        _ => return None,
+    };
+    Some(instruction)
+}
+
+impl Instruction {
+    // The inverse of `decode`, `decode(opcode).unwrap().encode() == opcode` for every known opcode.
+    // Registers and nibbles are cut down to 4 bits so they can't spill into the opcode group.
+    pub fn encode(&self) -> u16 {
+        fn nibble(value: u8) -> u16 {
+            (value & 0xF) as u16
+        }
+        fn xy(group: u16, x: u8, y: u8, n: u8) -> u16 {
+            group << 12 | nibble(x) << 8 | nibble(y) << 4 | nibble(n)
+        }
+        fn xnn(group: u16, x: u8, nn: u8) -> u16 {
+            group << 12 | nibble(x) << 8 | nn as u16
+        }
+        fn fx(x: u8, low: u16) -> u16 {
+            0xF000 | nibble(x) << 8 | low
+        }
+
+        match *self {
+            Instruction::Cls => 0x00E0,
+            Instruction::Ret => 0x00EE,
+            Instruction::Sys { nnn } => nnn & 0x0FFF,
+            Instruction::Jp { nnn } => 0x1000 | nnn & 0x0FFF,
+            Instruction::Call { nnn } => 0x2000 | nnn & 0x0FFF,
+            Instruction::SeByte { x, nn } => xnn(0x3, x, nn),
+            Instruction::SneByte { x, nn } => xnn(0x4, x, nn),
+            Instruction::Se { x, y } => xy(0x5, x, y, 0x0),
+            Instruction::LdByte { x, nn } => xnn(0x6, x, nn),
+            Instruction::AddByte { x, nn } => xnn(0x7, x, nn),
+            Instruction::Ld { x, y } => xy(0x8, x, y, 0x0),
+            Instruction::Or { x, y } => xy(0x8, x, y, 0x1),
+            Instruction::And { x, y } => xy(0x8, x, y, 0x2),
+            Instruction::Xor { x, y } => xy(0x8, x, y, 0x3),
+            Instruction::Add { x, y } => xy(0x8, x, y, 0x4),
+            Instruction::Sub { x, y } => xy(0x8, x, y, 0x5),
+            Instruction::Shr { x, y } => xy(0x8, x, y, 0x6),
+            Instruction::Subn { x, y } => xy(0x8, x, y, 0x7),
+            Instruction::Shl { x, y } => xy(0x8, x, y, 0xE),
+            Instruction::Sne { x, y } => xy(0x9, x, y, 0x0),
+            Instruction::LdI { nnn } => 0xA000 | nnn & 0x0FFF,
+            Instruction::JpV0 { nnn } => 0xB000 | nnn & 0x0FFF,
+            Instruction::Rnd { x, nn } => xnn(0xC, x, nn),
+            Instruction::Drw { x, y, n } => xy(0xD, x, y, n),
+            Instruction::Skp { x } => xnn(0xE, x, 0x9E),
+            Instruction::Sknp { x } => xnn(0xE, x, 0xA1),
+            Instruction::LdILong => 0xF000,
+            Instruction::LdVxDt { x } => fx(x, 0x07),
+            Instruction::LdVxK { x } => fx(x, 0x0A),
+            Instruction::LdDtVx { x } => fx(x, 0x15),
+            Instruction::LdStVx { x } => fx(x, 0x18),
+            Instruction::AddI { x } => fx(x, 0x1E),
+            Instruction::LdF { x } => fx(x, 0x29),
+            Instruction::LdHf { x } => fx(x, 0x30),
+            Instruction::LdB { x } => fx(x, 0x33),
+            Instruction::LdIVx { x } => fx(x, 0x55),
+            Instruction::LdVxI { x } => fx(x, 0x65),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(0x00E0), Some(Instruction::Cls));
+        assert_eq!(decode(0x8454), Some(Instruction::Add { x: 4, y: 5 }));
+        assert_eq!(decode(0x64AA), Some(Instruction::LdByte { x: 4, nn: 0xAA }));
+        assert_eq!(decode(0xD465), Some(Instruction::Drw { x: 4, y: 6, n: 5 }));
+        assert_eq!(decode(0x12DC), Some(Instruction::Jp { nnn: 0x2DC }));
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(Instruction::Cls.encode(), 0x00E0);
+        assert_eq!(Instruction::Add { x: 4, y: 5 }.encode(), 0x8454);
+        assert_eq!(Instruction::Drw { x: 4, y: 6, n: 5 }.encode(), 0xD465);
+        assert_eq!(Instruction::LdB { x: 0xA }.encode(), 0xFA33);
+    }
+
+    #[test]
+    fn test_encode_masks_out_of_range_fields() {
+        assert_eq!(Instruction::Drw { x: 0x10, y: 0x2, n: 0x15 }.encode(), 0xD025);
+        assert_eq!(Instruction::Add { x: 0x14, y: 0xF5 }.encode(), 0x8454);
+        assert_eq!(Instruction::LdByte { x: 0x1F, nn: 0xAA }.encode(), 0x6FAA);
+        assert_eq!(Instruction::LdB { x: 0x1A }.encode(), 0xFA33);
+    }
+
+    #[test]
+    fn test_encode_round_trips_every_known_opcode() {
+        for opcode in 0..=u16::MAX {
+            if let Some(instruction) = decode(opcode) {
+                assert_eq!(instruction.encode(), opcode, "{instruction:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_unknown_opcode() {
+        assert_eq!(decode(0x5001), None);
+        assert_eq!(decode(0xE000), None);
+    }
+}
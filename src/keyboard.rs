@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use alloc::collections::BTreeMap;
 
 /*
 Bindings:
@@ -34,37 +34,197 @@ const KEY_BINDINGS: [(u8, char); 16] = [
     (0xF, 'V')
 ];
 
+// Where the CPU gets its input from. `Keypad` is the built-in implementation front-ends push
+// key events into, but input can come from anything (SDL events, a scripted replay, a mock).
+pub trait InputSource {
+    // Whether the CHIP-8 key (0x0-0xF) is currently pressed.
+    fn is_pressed(&self, key: u8) -> bool;
+
+    // The next key press, if any, used by the blocking FX0A instruction.
+    fn wait_key(&mut self) -> Option<u8>;
+}
+
 pub struct Keypad {
-    mapping: HashMap<u8, char>,
+    mapping: BTreeMap<u8, char>,
+    // keys currently held down, bit N is set while key N is pressed
+    pressed_keys: u16,
+    // most recent press not yet consumed by FX0A
     last_pressed_key: Option<u8>
 }
 
-impl Keypad {
-    pub fn default() -> Self {
-        let mut mapping = HashMap::new();
+impl Default for Keypad {
+    fn default() -> Self {
+        let mut mapping = BTreeMap::new();
 
         for (hex_key, key_binding) in KEY_BINDINGS.iter() {
             mapping.insert(*hex_key, *key_binding);
         }
         Keypad {
             mapping,
+            pressed_keys: 0,
             last_pressed_key: None
         }
     }
+}
+
+impl Keypad {
+    // The keyboard key bound to the CHIP-8 key `hex`, e.g. to label an on-screen keypad.
+    pub fn char_for_hex(&self, hex: u8) -> Option<char> {
+        self.mapping.get(&hex).copied()
+    }
 
+    // Keys outside of 0x0-0xF are ignored.
     pub fn keypress(&mut self, key: u8) {
-        self.last_pressed_key = Some(key);
+        if key <= 0xF {
+            self.pressed_keys |= 1 << key;
+            self.last_pressed_key = Some(key);
+        }
+    }
+
+    pub fn remove_keypress(&mut self, key: u8) {
+        if key <= 0xF {
+            self.pressed_keys &= !(1 << key);
+        }
+        if self.last_pressed_key == Some(key) {
+            self.last_pressed_key = None;
+        }
+    }
+
+    // Releases every key, e.g. when the front-end window loses focus.
+    pub fn zero_keypresses(&mut self) {
+        self.pressed_keys = 0;
+        self.last_pressed_key = None;
     }
 
     pub fn was_key_pressed(&self, key: u8) -> bool {
-        self.last_pressed_key == Some(key)
+        key <= 0xF && self.pressed_keys & (1 << key) != 0
     }
 
     pub fn any_key_pressed(&self) -> bool {
-        self.last_pressed_key.is_some()
+        self.pressed_keys != 0
+    }
+
+    // The keys currently held down in ascending order, e.g. to highlight them on an on-screen keypad.
+    pub fn pressed(&self) -> impl Iterator<Item = u8> + '_ {
+        (0x0..=0xF).filter(|&key| self.was_key_pressed(key))
     }
 
     pub fn take_keypress(&mut self) -> Option<u8> {
         self.last_pressed_key.take()
     }
-}
\ No newline at end of file
+}
+
+impl InputSource for Keypad {
+    fn is_pressed(&self, key: u8) -> bool {
+        self.was_key_pressed(key)
+    }
+
+    fn wait_key(&mut self) -> Option<u8> {
+        self.take_keypress()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use super::*;
+
+    #[test]
+    fn test_pressed() {
+        let mut keypad = Keypad::default();
+
+        keypad.keypress(0xA);
+        keypad.keypress(0x1);
+
+        assert_eq!(keypad.pressed().collect::<Vec<u8>>(), vec![0x1, 0xA]);
+
+        keypad.remove_keypress(0xA);
+
+        assert_eq!(keypad.pressed().collect::<Vec<u8>>(), vec![0x1]);
+        assert!(keypad.was_key_pressed(0x1));
+        assert!(!keypad.was_key_pressed(0xA));
+    }
+
+    #[test]
+    fn test_char_for_hex() {
+        let keypad = Keypad::default();
+
+        assert_eq!(keypad.char_for_hex(0x4), Some('Q'));
+        assert_eq!(keypad.char_for_hex(0xF), Some('V'));
+        assert_eq!(keypad.char_for_hex(0x10), None);
+    }
+
+    #[test]
+    fn test_pressed_keys_behave_like_a_set() {
+        use std::collections::HashSet;
+
+        let mut keypad = Keypad::default();
+        let mut expected = HashSet::new();
+        let events = [(0x1, true), (0xA, true), (0x1, true), (0x3, false), (0x1, false), (0xF, true), (0xA, false)];
+
+        for (key, pressed) in events {
+            if pressed {
+                keypad.keypress(key);
+                expected.insert(key);
+            } else {
+                keypad.remove_keypress(key);
+                expected.remove(&key);
+            }
+
+            for key in 0x0..=0xF {
+                assert_eq!(keypad.was_key_pressed(key), expected.contains(&key));
+            }
+            assert_eq!(keypad.any_key_pressed(), !expected.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_any_key_pressed_follows_bitmask() {
+        let mut keypad = Keypad::default();
+
+        keypad.keypress(0x0);
+        keypad.keypress(0xF);
+
+        assert_eq!(keypad.pressed_keys, 0b1000_0000_0000_0001);
+        assert!(keypad.any_key_pressed());
+
+        keypad.remove_keypress(0x0);
+
+        assert_eq!(keypad.pressed_keys, 0b1000_0000_0000_0000);
+        assert!(keypad.any_key_pressed());
+
+        keypad.remove_keypress(0xF);
+
+        assert!(!keypad.any_key_pressed());
+
+        keypad.keypress(0x7);
+        keypad.zero_keypresses();
+
+        assert!(!keypad.any_key_pressed());
+        assert_eq!(keypad.take_keypress(), None);
+    }
+
+    #[test]
+    fn test_keys_out_of_range_are_ignored() {
+        let mut keypad = Keypad::default();
+
+        keypad.keypress(0x10);
+
+        assert!(!keypad.any_key_pressed());
+        assert!(!keypad.was_key_pressed(0x10));
+        assert_eq!(keypad.take_keypress(), None);
+    }
+
+    #[test]
+    fn test_held_keys_survive_take_keypress() {
+        let mut keypad = Keypad::default();
+
+        keypad.keypress(0x1);
+        keypad.keypress(0x2);
+
+        assert_eq!(keypad.take_keypress(), Some(0x2));
+        assert_eq!(keypad.take_keypress(), None);
+        assert!(keypad.was_key_pressed(0x1));
+        assert!(keypad.was_key_pressed(0x2));
+    }
+}
@@ -0,0 +1,23 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+// the test harness needs std anyway, this makes its macros and types available to tests without the feature.
+#[cfg(all(test, not(feature = "std")))]
+#[macro_use]
+extern crate std;
+
+pub mod memory;
+pub mod screen;
+pub mod keyboard;
+pub mod cpu;
+pub mod quirks;
+#[cfg(feature = "std")]
+pub mod assembler;
+pub mod disassembler;
+pub mod instruction;
+pub mod error;
+pub mod rom;
+mod ihex;
+
+#[cfg(test)]
+mod test_logger;
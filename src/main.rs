@@ -1,9 +1,4 @@
-mod memory;
-mod screen;
-mod keyboard;
-mod cpu;
-
-use crate::cpu::Chip8;
+use chip_8::cpu::Chip8;
 
 fn main() {
     let instructions_to_store_in_memory: [u8; 6] = [0x80, 0x14, 0x80, 0x24, 0x80, 0x34];
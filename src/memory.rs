@@ -1,46 +1,124 @@
-use std::error::Error;
-use std::fmt::{Debug, Display, Formatter};
-use std::ops::{Index, IndexMut};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::{Index, IndexMut, Range};
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+use crate::error::Chip8Error;
 
+#[cfg(not(feature = "xo-chip"))]
 const MEMORY: usize = 4096;   // 4KB
+#[cfg(feature = "xo-chip")]
+const MEMORY: usize = 65536;  // 64KB, addressable through XO-CHIP's `F000 NNNN`
 const LOWER_MEMORY_BOUNDARY: usize = 512;
+// everything a 16-bit address can reach
+const MAX_MEMORY: usize = 0x10000;
 
-#[derive(Debug)]
-struct ProgramTooLargeError;
+#[derive(Clone)]
+pub struct Memory {
+    memory: Vec<u8>,
+}
 
-impl Display for ProgramTooLargeError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Provided program is too large to fit into memory.")
+impl Default for Memory {
+    fn default() -> Self {
+        Memory::with_size(MEMORY)
     }
 }
 
+impl Memory {
+    // `size` bytes of memory, e.g. 65536 for XO-CHIP. It is kept between the start of programs
+    // at 0x200 and the 64KB a 16-bit address can reach.
+    pub fn with_size(size: usize) -> Self {
+        Memory { memory: vec![0x0; size.clamp(LOWER_MEMORY_BOUNDARY, MAX_MEMORY)] }
+    }
 
-impl Error for ProgramTooLargeError {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        None
+    pub fn size(&self) -> usize {
+        self.memory.len()
     }
-}
 
-pub struct Memory {
-    memory: [u8; MEMORY],
-}
+    pub fn read_byte(&self, addr: u16) -> Option<u8> {
+        self.memory.get(addr as usize).copied()
+    }
 
-impl Memory {
+    // Every byte from address 0 to the end of memory, e.g. for a hexdump.
+    pub fn iter(&self) -> impl Iterator<Item = &u8> {
+        self.memory.iter()
+    }
+
+    pub fn load_fontset(&mut self, font: &[u8], start: u16) -> Result<(), Chip8Error> {
+        let begin = start as usize;
+        if begin + font.len() > self.size() {
+            return Err(Chip8Error::AddressOutOfBounds { addr: start, len: font.len() });
+        }
+
+        self.memory[begin..begin + font.len()].copy_from_slice(font);
+        Ok(())
+    }
 
-    pub fn default() -> Self {
-        Memory{memory: [0x0;MEMORY]}
+    pub fn load_program(&mut self, program: impl AsRef<[u8]>) -> Result<(), Chip8Error> {
+        self.load_program_at(program, LOWER_MEMORY_BOUNDARY as u16)
     }
 
-    pub fn load_program(&mut self, program: Vec<u8>) -> Result<(), Box<dyn Error>> {
-        if program.len() + LOWER_MEMORY_BOUNDARY > MEMORY {
-            return Err(Box::new(ProgramTooLargeError));
+    // Odd-length programs are still loaded, the last opcode is completed by the zero byte following it.
+    // Since this usually means the file is corrupt or not a CHIP-8 ROM, a warning gets logged.
+    pub fn load_program_at(&mut self, program: impl AsRef<[u8]>, start: u16) -> Result<(), Chip8Error> {
+        let program = program.as_ref();
+        let start = start as usize;
+        if program.len() + start > self.size() {
+            return Err(Chip8Error::ProgramTooLarge {
+                program_len: program.len(),
+                available: self.size().saturating_sub(start),
+            });
+        }
+
+        if !program.len().is_multiple_of(2) {
+            log::warn!("program has an odd length of {} bytes", program.len());
         }
 
-        for (idx, instruction) in program.iter().copied().enumerate() {
-            self.memory[LOWER_MEMORY_BOUNDARY + idx] = instruction
+        self.memory[start..start + program.len()].copy_from_slice(program);
+        Ok(())
+    }
+
+    // Zeroes everything from `start` to the end of memory.
+    pub(crate) fn clear_from(&mut self, start: u16) {
+        let start = (start as usize).min(self.size());
+        self.memory[start..].fill(0);
+    }
+
+    // 32-bit FNV-1a hash of `len` bytes starting at `start`, e.g. to identify a loaded ROM.
+    // The range is cut off at the end of memory.
+    pub fn checksum(&self, start: u16, len: u16) -> u32 {
+        let start = (start as usize).min(self.size());
+        let end = (start + len as usize).min(self.size());
+        fnv1a(&self.memory[start..end])
+    }
+
+    // Overwrites memory at `addr` with `bytes`, e.g. a sprite for drawing tests.
+    pub fn write_sprite(&mut self, addr: u16, bytes: &[u8]) -> Result<(), Chip8Error> {
+        let start = addr as usize;
+        if start + bytes.len() > self.size() {
+            return Err(Chip8Error::AddressOutOfBounds { addr, len: bytes.len() });
         }
+
+        self.memory[start..start + bytes.len()].copy_from_slice(bytes);
         Ok(())
     }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn save_to_writer<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&self.memory)
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn load_from_reader<R: Read>(&mut self, r: &mut R) -> io::Result<()> {
+        r.read_exact(&mut self.memory)
+    }
+}
+
+// 32-bit FNV-1a hash of `bytes`.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0x811C_9DC5, |hash, &byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
 }
 
 impl Index<u16> for Memory {
@@ -51,8 +129,166 @@ impl Index<u16> for Memory {
     }
 }
 
+// `&memory[0x200..0x210]`, panics like a slice if the range runs past the end of memory.
+impl Index<Range<u16>> for Memory {
+    type Output = [u8];
+
+    fn index(&self, range: Range<u16>) -> &Self::Output {
+        &self.memory[range.start as usize..range.end as usize]
+    }
+}
+
 impl IndexMut<u16> for Memory {
     fn index_mut(&mut self, index: u16) -> &mut Self::Output {
         self.memory.get_mut(index as usize).unwrap()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use super::*;
+
+    #[test]
+    fn test_read_byte() {
+        let mut memory = Memory::default();
+
+        memory.load_program(vec![0xAB]).unwrap();
+
+        assert_eq!(memory.read_byte(LOWER_MEMORY_BOUNDARY as u16), Some(0xAB));
+        assert_eq!(memory.read_byte(0x0), Some(0x0));
+    }
+
+    #[test]
+    fn test_load_program_at_custom_address() {
+        let mut memory = Memory::default();
+
+        memory.load_program_at(vec![0x12, 0x34, 0x56], 0x600).unwrap();
+
+        assert_eq!(memory[0x600], 0x12);
+        assert_eq!(memory[0x601], 0x34);
+        assert_eq!(memory[0x602], 0x56);
+        assert_eq!(memory[LOWER_MEMORY_BOUNDARY as u16], 0x0);
+    }
+
+    #[test]
+    fn test_range_index_and_iter() {
+        let mut memory = Memory::with_size(4096);
+
+        memory.load_program(vec![0x60, 0x0A, 0xD0, 0x15]).unwrap();
+
+        assert_eq!(&memory[0x200..0x204], &[0x60, 0x0A, 0xD0, 0x15]);
+        assert_eq!(&memory[0x202..0x202], &[] as &[u8]);
+        assert_eq!(memory.iter().count(), 4096);
+        assert_eq!(memory.iter().skip(0x200).take(2).copied().collect::<Vec<u8>>(), vec![0x60, 0x0A]);
+    }
+
+    #[test]
+    fn test_load_program_at_that_is_too_big() {
+        let mut memory = Memory::default();
+
+        let result = memory.load_program_at(vec![0; MEMORY - 0x600 + 1], 0x600);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_memory_with_size() {
+        let mut memory = Memory::with_size(65536);
+
+        memory.load_program_at(vec![0x12, 0x34], 0x1000).unwrap();
+
+        assert_eq!(memory.size(), 65536);
+        assert_eq!(memory[0x1001], 0x34);
+        assert!(memory.load_program_at(vec![0; 0x10000 - 0x1000], 0x1000).is_ok());
+        assert_eq!(
+            memory.load_program_at(vec![0; 0x10000 - 0x1000 + 1], 0x1000),
+            Err(Chip8Error::ProgramTooLarge { program_len: 0xF001, available: 0xF000 })
+        );
+    }
+
+    #[test]
+    fn test_memory_size_is_clamped() {
+        assert_eq!(Memory::with_size(0x100).size(), 0x200);
+        assert_eq!(Memory::with_size(0x20000).size(), 0x10000);
+    }
+
+    #[test]
+    fn test_program_too_large_message() {
+        let mut memory = Memory::with_size(4096);
+
+        let error = memory.load_program(vec![0; 4400]).unwrap_err();
+
+        assert_eq!(error, Chip8Error::ProgramTooLarge { program_len: 4400, available: 3584 });
+        assert_eq!(error.to_string(), "Program is 4400 bytes but only 3584 bytes are available.");
+    }
+
+    #[test]
+    fn test_load_odd_length_program_warns() {
+        crate::test_logger::start_capture();
+        let mut memory = Memory::default();
+
+        memory.load_program(vec![0x60, 0x01, 0x70]).unwrap();
+
+        assert_eq!(memory[0x202], 0x70);
+        assert_eq!(memory[0x203], 0x0);
+        assert_eq!(
+            crate::test_logger::captured(),
+            vec![(log::Level::Warn, "program has an odd length of 3 bytes".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_load_even_length_program_does_not_warn() {
+        crate::test_logger::start_capture();
+        let mut memory = Memory::default();
+
+        memory.load_program(vec![0x60, 0x01]).unwrap();
+
+        assert!(crate::test_logger::captured().is_empty());
+    }
+
+    #[test]
+    fn test_checksum() {
+        let mut memory = Memory::default();
+
+        memory.load_program([0x60, 0x05, 0x70, 0x01]).unwrap();
+
+        assert_eq!(memory.checksum(0x200, 4), 0xAC64_ED81);
+        // an empty range is the FNV offset basis
+        assert_eq!(memory.checksum(0x200, 0), 0x811C_9DC5);
+        assert_eq!(memory.checksum((MEMORY - 2) as u16, 10), memory.checksum((MEMORY - 2) as u16, 2));
+    }
+
+    #[test]
+    fn test_write_sprite() {
+        let mut memory = Memory::default();
+
+        memory.write_sprite(0x300, &[0xF0, 0x90]).unwrap();
+
+        assert_eq!(memory[0x300], 0xF0);
+        assert_eq!(memory[0x301], 0x90);
+    }
+
+    #[test]
+    fn test_write_sprite_out_of_bounds() {
+        let mut memory = Memory::default();
+        let addr = (MEMORY - 1) as u16;
+
+        let result = memory.write_sprite(addr, &[0xF0, 0x90]);
+
+        assert_eq!(result, Err(Chip8Error::AddressOutOfBounds { addr, len: 2 }));
+        assert_eq!(memory[addr], 0x0);
+    }
+
+    #[test]
+    fn test_load_fontset_out_of_bounds() {
+        let mut memory = Memory::default();
+        let addr = (MEMORY - 40) as u16;
+
+        let result = memory.load_fontset(&[0xF0; 80], addr);
+
+        assert_eq!(result, Err(Chip8Error::AddressOutOfBounds { addr, len: 80 }));
+        assert_eq!(memory[addr], 0x0);
+    }
+}
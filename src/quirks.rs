@@ -0,0 +1,167 @@
+// Behaviors that differ between CHIP-8 platforms. The defaults match the modern interpretation
+// most ROMs written today expect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quirks {
+    // 8XY6/8XYE shift VY and store the result in VX, instead of shifting VX in place.
+    pub shift_uses_vy: bool,
+    // FX55/FX65 leave I pointing right after the last register stored or loaded.
+    pub load_store_increments_i: bool,
+    // BNNN is read as BXNN: jump to XNN plus VX instead of NNN plus V0.
+    pub jump_uses_vx: bool,
+    // DXYN waits for the vertical blank, so at most one sprite is drawn per frame.
+    pub display_wait: bool,
+    // DXYN sets VF to the number of sprite rows that collided instead of just 0 or 1, as in XO-CHIP.
+    pub collision_counts_rows: bool,
+    // FX1E wraps I around within the 12-bit address space, as on the Amiga interpreter.
+    pub wrap_index_register: bool,
+    // DXYN cuts off sprite pixels past the right and bottom edges instead of wrapping them around.
+    pub clip_sprites: bool,
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks::modern()
+    }
+}
+
+impl Quirks {
+    // The original interpreter on the COSMAC VIP.
+    pub fn cosmac_vip() -> Self {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            display_wait: true,
+            collision_counts_rows: false,
+            wrap_index_register: false,
+            clip_sprites: true,
+        }
+    }
+
+    // SUPER-CHIP 1.1 on the HP48 calculators.
+    pub fn super_chip() -> Self {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: true,
+            display_wait: false,
+            collision_counts_rows: false,
+            wrap_index_register: false,
+            clip_sprites: true,
+        }
+    }
+
+    // XO-CHIP as implemented by Octo.
+    pub fn xo_chip() -> Self {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            display_wait: false,
+            collision_counts_rows: true,
+            wrap_index_register: false,
+            clip_sprites: false,
+        }
+    }
+
+    // What most modern interpreters and ROMs settled on.
+    pub fn modern() -> Self {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: false,
+            display_wait: false,
+            collision_counts_rows: false,
+            wrap_index_register: false,
+            clip_sprites: true,
+        }
+    }
+}
+
+// The machines ROMs are written for, each with its own set of quirks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Platform {
+    CosmacVip,
+    SuperChip,
+    XoChip,
+    Modern,
+}
+
+impl Platform {
+    pub fn quirks(self) -> Quirks {
+        match self {
+            Platform::CosmacVip => Quirks::cosmac_vip(),
+            Platform::SuperChip => Quirks::super_chip(),
+            Platform::XoChip => Quirks::xo_chip(),
+            Platform::Modern => Quirks::modern(),
+        }
+    }
+
+    // The usual speed of programs for the platform, in instructions per second.
+    pub fn default_clock_hz(self) -> u32 {
+        match self {
+            Platform::CosmacVip => 540,
+            Platform::SuperChip => 1000,
+            Platform::XoChip => 1000,
+            Platform::Modern => 600,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cosmac_vip_preset() {
+        let quirks = Quirks::cosmac_vip();
+
+        assert!(quirks.shift_uses_vy);
+        assert!(quirks.load_store_increments_i);
+        assert!(!quirks.jump_uses_vx);
+        assert!(quirks.display_wait);
+        assert!(!quirks.collision_counts_rows);
+        assert!(!quirks.wrap_index_register);
+        assert!(quirks.clip_sprites);
+    }
+
+    #[test]
+    fn test_super_chip_preset() {
+        let quirks = Quirks::super_chip();
+
+        assert!(!quirks.shift_uses_vy);
+        assert!(!quirks.load_store_increments_i);
+        assert!(quirks.jump_uses_vx);
+        assert!(!quirks.display_wait);
+        assert!(!quirks.collision_counts_rows);
+        assert!(!quirks.wrap_index_register);
+        assert!(quirks.clip_sprites);
+    }
+
+    #[test]
+    fn test_xo_chip_preset() {
+        let quirks = Quirks::xo_chip();
+
+        assert!(quirks.shift_uses_vy);
+        assert!(quirks.load_store_increments_i);
+        assert!(!quirks.jump_uses_vx);
+        assert!(!quirks.display_wait);
+        assert!(quirks.collision_counts_rows);
+        assert!(!quirks.wrap_index_register);
+        assert!(!quirks.clip_sprites);
+    }
+
+    #[test]
+    fn test_modern_preset() {
+        let quirks = Quirks::modern();
+
+        assert!(!quirks.shift_uses_vy);
+        assert!(!quirks.load_store_increments_i);
+        assert!(!quirks.jump_uses_vx);
+        assert!(!quirks.display_wait);
+        assert!(!quirks.collision_counts_rows);
+        assert!(!quirks.wrap_index_register);
+        assert!(quirks.clip_sprites);
+        assert_eq!(Quirks::default(), quirks);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::memory::fnv1a;
+use crate::quirks::Platform;
+
+// What can be told about a ROM before running it, e.g. to preselect the quirks in a front-end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RomInfo {
+    pub size: usize,
+    // same hash as `Chip8::rom_checksum` once the ROM is loaded
+    pub checksum: u32,
+    pub suspected_platform: Platform,
+}
+
+// Guesses the platform from the instructions only the extensions have. This is a heuristic: data
+// that happens to look like one of them gives a false positive, ROMs without any count as COSMAC VIP.
+pub fn analyze_rom(rom: &[u8]) -> RomInfo {
+    let mut suspected_platform = Platform::CosmacVip;
+    for word in rom.chunks_exact(2) {
+        let opcode = u16::from_be_bytes([word[0], word[1]]);
+        if is_xo_chip_only(opcode) {
+            // XO-CHIP is a superset of SUPER-CHIP, nothing can rule it out again.
+            suspected_platform = Platform::XoChip;
+            break;
+        }
+        if is_super_chip_only(opcode) {
+            suspected_platform = Platform::SuperChip;
+        }
+    }
+
+    RomInfo { size: rom.len(), checksum: fnv1a(rom), suspected_platform }
+}
+
+// 00CN, 00FB-00FF, DXY0, FX30, FX75 and FX85
+fn is_super_chip_only(opcode: u16) -> bool {
+    opcode & 0xFFF0 == 0x00C0
+        || matches!(opcode, 0x00FB..=0x00FF)
+        || opcode & 0xF00F == 0xD000
+        || matches!(opcode & 0xF0FF, 0xF030 | 0xF075 | 0xF085)
+}
+
+// 00DN, 5XY2, 5XY3, F000 NNNN, FN01 and F002
+fn is_xo_chip_only(opcode: u16) -> bool {
+    opcode & 0xFFF0 == 0x00D0
+        || matches!(opcode & 0xF00F, 0x5002 | 0x5003)
+        || matches!(opcode, 0xF000 | 0xF002)
+        || opcode & 0xF0FF == 0xF001
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_classic_rom() {
+        let info = analyze_rom(&[0x60, 0x05, 0x70, 0x01, 0xD0, 0x15]);
+
+        assert_eq!(info.size, 6);
+        assert_eq!(info.suspected_platform, Platform::CosmacVip);
+    }
+
+    #[test]
+    fn test_analyze_super_chip_rom() {
+        let info = analyze_rom(&[0x00, 0xFF, 0x60, 0x05, 0xD0, 0x10]);
+
+        assert_eq!(info.suspected_platform, Platform::SuperChip);
+    }
+
+    #[test]
+    fn test_analyze_xo_chip_rom() {
+        let info = analyze_rom(&[0x00, 0xFF, 0xF0, 0x00, 0x12, 0x34]);
+
+        assert_eq!(info.suspected_platform, Platform::XoChip);
+    }
+
+    #[test]
+    fn test_checksum_matches_loaded_rom() {
+        let rom = [0x60, 0x05, 0x70, 0x01];
+        let mut chip8 = crate::cpu::Chip8::new();
+        chip8.load_into_memory(rom).unwrap();
+
+        assert_eq!(analyze_rom(&rom).checksum, chip8.rom_checksum());
+    }
+}
@@ -1,20 +1,115 @@
-const SCREEN_WIDTH: usize = 64;
-const SCREEN_HEIGHT: usize = 32;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+pub(crate) const SCREEN_WIDTH: usize = 64;
+pub(crate) const SCREEN_HEIGHT: usize = 32;
+// the SUPER-CHIP high resolution mode, the buffers are sized for it
+const HIRES_WIDTH: usize = 128;
+pub(crate) const HIRES_HEIGHT: usize = 64;
+// byte-per-pixel values of the save state format
+#[cfg(any(feature = "std", test))]
 const PIXEL_ON: u8 = 0x01;
+#[cfg(any(feature = "std", test))]
 const PIXEL_OFF: u8 = 0x00;
+// XO-CHIP draws on two overlaid bit-planes, the classic CHIP-8 only uses the first one.
+const PLANES: usize = 2;
+
+// The classic 64x32 display or the SUPER-CHIP 128x64 one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScreenMode {
+    #[default]
+    Lores,
+    Hires,
+}
+
+impl ScreenMode {
+    pub fn dimensions(self) -> (usize, usize) {
+        match self {
+            ScreenMode::Lores => (SCREEN_WIDTH, SCREEN_HEIGHT),
+            ScreenMode::Hires => (HIRES_WIDTH, HIRES_HEIGHT),
+        }
+    }
+}
 
+// Each row is packed into a u128, the leftmost pixel being the most significant bit.
+// In lores mode only the top-left 64x32 pixels are used.
+#[derive(Clone)]
 pub struct Screen {
-    screen: [[u8; SCREEN_WIDTH]; SCREEN_HEIGHT],
-    collision: bool
+    screen: [[u128; HIRES_HEIGHT]; PLANES],
+    mode: ScreenMode,
+    // bitmask of the planes drawing operations apply to (bit 0 = plane 1, bit 1 = plane 2)
+    active_plane: u8,
+    // the lit pixels as of the last call to `take_dirty`, i.e. what a renderer using it shows
+    shown: [u128; HIRES_HEIGHT],
+    // set by anything changing the screen until `take_redraw` is called
+    redraw_needed: bool,
 }
 
-impl Screen {
-    pub fn default() -> Self {
+impl Default for Screen {
+    fn default() -> Self {
         Screen {
-            screen: [[PIXEL_OFF; SCREEN_WIDTH]; SCREEN_HEIGHT],
-            collision: false
+            screen: [[0; HIRES_HEIGHT]; PLANES],
+            mode: ScreenMode::default(),
+            active_plane: 0b01,
+            shown: [0; HIRES_HEIGHT],
+            redraw_needed: false,
         }
     }
+}
+
+impl Screen {
+
+    // Width and height of the display in pixels, e.g. to size a front-end window.
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.mode.dimensions()
+    }
+
+    pub fn mode(&self) -> ScreenMode {
+        self.mode
+    }
+
+    // Switches between lores and hires. Like the SUPER-CHIP hardware this clears the display
+    // on every plane, whatever was drawn before is gone even when switching back.
+    pub fn set_mode(&mut self, mode: ScreenMode) {
+        self.mode = mode;
+        self.screen = [[0; HIRES_HEIGHT]; PLANES];
+        // the whole display changed size, front-ends have to redraw everything: pretend it was all lit.
+        let (columns, height) = (self.visible_columns(), self.dimensions().1);
+        self.shown = core::array::from_fn(|y| if y < height { columns } else { 0 });
+        self.redraw_needed = true;
+    }
+
+    // The bits of a packed row that are on the display in the current mode.
+    fn visible_columns(&self) -> u128 {
+        !u128::MAX.checked_shr(self.dimensions().0 as u32).unwrap_or(0)
+    }
+
+    // Selects the plane(s) affected by drawing and clearing, as done by the XO-CHIP `FN01` instruction.
+    pub fn set_active_plane(&mut self, mask: u8) {
+        self.active_plane = mask & 0b11;
+    }
+
+    fn active_planes(&self) -> impl Iterator<Item = usize> {
+        let mask = self.active_plane;
+        (0..PLANES).filter(move |plane| mask & (1 << plane) != 0)
+    }
+
+    // a pixel is visible if it is lit on any of the planes.
+    fn is_lit(&self, x: usize, y: usize) -> bool {
+        self.screen.iter().any(|plane| plane[y] & pixel_bit(x) != 0)
+    }
+
+    // Color index of a pixel: bit 0 is set if it is lit on plane 1, bit 1 if it is lit on plane 2.
+    // Classic CHIP-8 programs only ever produce 0 (off) and 1 (on).
+    pub fn get_pixel_color(&self, x: usize, y: usize) -> u8 {
+        (0..PLANES)
+            .filter(|&plane| self.screen[plane][y] & pixel_bit(x) != 0)
+            .fold(0, |color, plane| color | (1 << plane))
+    }
 
     pub fn draw_sprite_at_location(
         &mut self,
@@ -22,67 +117,570 @@ impl Screen {
         x_coord: u8,
         y_coord: u8,
     ) -> bool {
-        for xline in 0..8 {
-            // this loop will scan through each bit in pixel_value and extract its content
-            // which is then used to determine if a collision occurred and will flag it in the register
-
-
-            // value of a pixel at a specific location.
-            let pixel_value = pixel & (0x80 >> xline);
-
-            // TODO: for some reason, everyone checks if pixel_value != 0 and only then draws the pixel?
-            if pixel_value != 0 {
-                // check if collision occurred which occurs when a pixel changed
-                // from 1 to 0 during a XOR operation.
-                let collision = self.screen[y_coord as usize][(x_coord + xline) as usize] == PIXEL_ON;
-                match collision {
-                    true => self.flag_collision(),
-                    false => ()
-                }
+        self.draw_sprite_row(&[pixel], x_coord, y_coord, 8)
+    }
 
-                // draw pixel value at location now
-                self.draw_pixel_at_location(
-                    x_coord + xline,
-                    y_coord,
-                );
-            }
+    // Draws the first `width_bits` bits of `bytes` (MSB first, up to 128) as one row starting at (x, y),
+    // e.g. two bytes with a width of 16 for the XO-CHIP 16x16 sprites.
+    pub fn draw_sprite_row(&mut self, bytes: &[u8], x_coord: u8, y_coord: u8, width_bits: u32) -> bool {
+        let packed = bytes
+            .iter()
+            .take(16)
+            .enumerate()
+            .fold(0u128, |row, (index, &byte)| row | (byte as u128) << (120 - 8 * index));
+        let packed = packed & !(u128::MAX.checked_shr(width_bits).unwrap_or(0));
+        // the sprite row moved to its place in the screen row, bits past the right edge fall off.
+        let sprite_row = packed.checked_shr(x_coord as u32).unwrap_or(0) & self.visible_columns();
+        self.draw_row(sprite_row, y_coord as usize)
+    }
+
+    // Like `draw_sprite_at_location`, but bits past the right edge wrap around to the left edge.
+    pub fn draw_sprite_at_location_wrapped(&mut self, pixel: u8, x_coord: u8, y_coord: u8) -> bool {
+        let width = self.dimensions().0;
+        let shift = x_coord as u32 % width as u32;
+        let sprite_row = (pixel as u128) << 120;
+        // the bits shifted out on the right come back in at the left edge of the display.
+        let wrapped = sprite_row.checked_shl(width as u32 - shift).unwrap_or(0);
+        let sprite_row = (sprite_row >> shift | wrapped) & self.visible_columns();
+        self.draw_row(sprite_row, y_coord as usize)
+    }
+
+    fn draw_row(&mut self, sprite_row: u128, y: usize) -> bool {
+        // collision is tracked for the whole row: it is set if any bit that was on
+        // got turned off on any of the selected planes, regardless of how many other bits were newly lit.
+        let collision = self.active_planes().any(|plane| self.screen[plane][y] & sprite_row != 0);
+
+        for plane in self.active_planes() {
+            self.screen[plane][y] ^= sprite_row;
+        }
+        if sprite_row != 0 {
+            self.redraw_needed = true;
         }
-        let collision = self.collision.clone();
-        self.reset_collision();
         collision
     }
 
-    fn flag_collision(&mut self) {
-        self.collision = true;
+    pub fn draw_pixel_at_location(&mut self, x: u8, y: u8) {
+        let bit = pixel_bit(x as usize);
+        for plane in self.active_planes() {
+            self.screen[plane][y as usize] ^= bit;
+        }
+        self.redraw_needed = true;
     }
 
-    fn reset_collision(&mut self) {
-        self.collision = false;
+    // The lit pixels of each row, whatever plane they are on.
+    pub(crate) fn lit_rows(&self) -> [u128; HIRES_HEIGHT] {
+        core::array::from_fn(|y| self.screen.iter().fold(0, |row, plane| row | plane[y]))
     }
 
-    pub fn draw_pixel_at_location(&mut self, x: u8, y: u8) {
-        self.screen[y as usize][x as usize] ^= PIXEL_ON;
+    // Whether the screen changed since the last call, so a render loop only repaints when needed.
+    pub fn take_redraw(&mut self) -> bool {
+        core::mem::take(&mut self.redraw_needed)
     }
 
-    pub fn how_many_ones(&self) -> u8 {
-        let mut counter : u8= 0;
-        for row in self.screen.iter() {
-            for pixel_value in row.iter() {
-                match pixel_value {
-                    &PIXEL_ON => counter += 1,
-                    _ => ()
-                }
+    pub fn how_many_ones(&self) -> usize {
+        (0..HIRES_HEIGHT)
+            .map(|y| self.screen.iter().fold(0, |row, plane| row | plane[y]).count_ones() as usize)
+            .sum()
+    }
+
+    // Turns every pixel of the active planes on.
+    pub fn fill(&mut self) {
+        let (columns, height) = (self.visible_columns(), self.dimensions().1);
+        for plane in self.active_planes() {
+            for row in self.screen[plane].iter_mut().take(height) {
+                *row = columns;
+            }
+        }
+        self.redraw_needed = true;
+    }
+
+    // Flips every pixel of the active planes, e.g. for a flash effect.
+    pub fn invert(&mut self) {
+        let (columns, height) = (self.visible_columns(), self.dimensions().1);
+        for plane in self.active_planes() {
+            for row in self.screen[plane].iter_mut().take(height) {
+                *row ^= columns;
             }
         }
-        counter
+        self.redraw_needed = true;
     }
 
     pub fn clear_screen(&mut self) {
-        for row in self.screen.iter_mut() {
-            for pixel_value in row.iter_mut() {
-                *pixel_value = 0;
+        for plane in self.active_planes() {
+            for row in self.screen[plane].iter_mut() {
+                *row = 0;
             }
         }
+        self.redraw_needed = true;
     }
 
+    #[cfg(feature = "std")]
+    pub(crate) fn save_to_writer<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        // one byte per pixel of the current mode, as the save state format predates the packed rows.
+        let (width, height) = self.dimensions();
+        w.write_all(&[self.active_plane, (self.mode == ScreenMode::Hires) as u8])?;
+        for &row in self.screen.iter().flat_map(|plane| &plane[..height]) {
+            let mut pixels = [PIXEL_OFF; HIRES_WIDTH];
+            let pixels = &mut pixels[..width];
+            for (x, pixel) in pixels.iter_mut().enumerate() {
+                if row & pixel_bit(x) != 0 {
+                    *pixel = PIXEL_ON;
+                }
+            }
+            w.write_all(pixels)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn load_from_reader<R: Read>(&mut self, r: &mut R) -> io::Result<()> {
+        let mut header = [0; 2];
+        r.read_exact(&mut header)?;
+        self.set_mode(if header[1] == 1 { ScreenMode::Hires } else { ScreenMode::Lores });
+        self.active_plane = header[0] & 0b11;
+
+        let (width, height) = self.dimensions();
+        for row in self.screen.iter_mut().flat_map(|plane| &mut plane[..height]) {
+            let mut pixels = [PIXEL_OFF; HIRES_WIDTH];
+            let pixels = &mut pixels[..width];
+            r.read_exact(pixels)?;
+            *row = pixels
+                .iter()
+                .enumerate()
+                .filter(|&(_, &pixel)| pixel == PIXEL_ON)
+                .fold(0, |row, (x, _)| row | pixel_bit(x));
+        }
+        Ok(())
+    }
+
+    // Every pixel of the display as (x, y, lit), row by row, e.g. `.filter(|&(_, _, lit)| lit)` to only draw lit ones.
+    pub fn iter_pixels(&self) -> impl Iterator<Item = (usize, usize, bool)> + '_ {
+        let (width, height) = self.dimensions();
+        (0..height).flat_map(move |y| (0..width).map(move |x| (x, y, self.is_lit(x, y))))
+    }
+
+    // The display as a plain (P1) portable bitmap, 1 for a lit pixel. Rows are split into lines
+    // of at most 64 digits to stay below the 70 characters the format allows per line.
+    pub fn to_pbm(&self) -> String {
+        let (width, height) = self.dimensions();
+        let mut pbm = format!("P1\n{width} {height}\n");
+        for y in 0..height {
+            for x in 0..width {
+                pbm.push(if self.is_lit(x, y) { '1' } else { '0' });
+                if (x + 1) % SCREEN_WIDTH == 0 {
+                    pbm.push('\n');
+                }
+            }
+        }
+        pbm
+    }
+
+    // Returns every (x, y, new_state) pixel that is lit differently than at the last call,
+    // so a renderer only needs to update the cells that changed. A pixel drawn twice doesn't count.
+    pub fn take_dirty(&mut self) -> Vec<(usize, usize, bool)> {
+        let (width, height) = self.dimensions();
+        let lit = self.lit_rows();
+        let mut changed = Vec::new();
+        for (y, (&row, &shown)) in lit.iter().zip(&self.shown).enumerate().take(height) {
+            for x in 0..width {
+                if (row ^ shown) & pixel_bit(x) != 0 {
+                    changed.push((x, y, row & pixel_bit(x) != 0));
+                }
+            }
+        }
+        self.shown = lit;
+        changed
+    }
+}
+
+// The bit of pixel `x` in a packed row.
+fn pixel_bit(x: usize) -> u128 {
+    1 << (HIRES_WIDTH - 1 - x)
+}
+
+// Prints the display as ASCII art, one line per row: `#` for a lit pixel and `.` for an unlit one.
+impl Display for Screen {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let (width, height) = self.dimensions();
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = if self.is_lit(x, y) { '#' } else { '.' };
+                write!(f, "{pixel}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_dirty_returns_changed_pixels_once() {
+        let mut screen = Screen::default();
+
+        screen.draw_pixel_at_location(3, 4);
+        screen.draw_pixel_at_location(10, 20);
+
+        let dirty = screen.take_dirty();
+
+        assert_eq!(dirty.len(), 2);
+        assert!(dirty.contains(&(3, 4, true)));
+        assert!(dirty.contains(&(10, 20, true)));
+
+        assert_eq!(screen.take_dirty().len(), 0);
+    }
+
+    #[test]
+    fn test_draw_sprite_collision_on_single_overlapping_bit() {
+        let mut screen = Screen::default();
+
+        assert!(!screen.draw_sprite_at_location(0b0001_0000, 0, 0));
+
+        // overlaps the existing row in exactly one bit while lighting three new ones.
+        let collision = screen.draw_sprite_at_location(0b1111_0000, 0, 0);
+
+        assert!(collision);
+        assert_eq!(screen.how_many_ones(), 3);
+    }
+
+    #[test]
+    fn test_draw_sprite_without_overlap_has_no_collision() {
+        let mut screen = Screen::default();
+
+        screen.draw_sprite_at_location(0b0000_1111, 0, 0);
+
+        assert!(!screen.draw_sprite_at_location(0b1111_0000, 0, 0));
+        assert_eq!(screen.how_many_ones(), 8);
+    }
+
+    #[test]
+    fn test_draw_sixteen_pixel_wide_row() {
+        let mut screen = Screen::default();
+
+        assert!(!screen.draw_sprite_row(&[0xFF, 0xFF], 4, 2, 16));
+
+        assert!((4..20).all(|x| screen.get_pixel_color(x, 2) == 1));
+        assert_eq!(screen.how_many_ones(), 16);
+
+        // only the last pixel of the second byte overlaps.
+        assert!(screen.draw_sprite_row(&[0x00, 0x01, 0xFF], 4, 2, 16));
+        assert_eq!(screen.get_pixel_color(19, 2), 0);
+        assert_eq!(screen.how_many_ones(), 15);
+    }
+
+    #[test]
+    fn test_how_many_ones_on_full_screen() {
+        let mut screen = Screen::default();
+
+        for y in 0..SCREEN_HEIGHT {
+            for x in 0..SCREEN_WIDTH {
+                screen.draw_pixel_at_location(x as u8, y as u8);
+            }
+        }
+
+        assert_eq!(screen.how_many_ones(), SCREEN_WIDTH * SCREEN_HEIGHT);
+    }
+
+    #[test]
+    fn test_take_redraw() {
+        let mut screen = Screen::default();
+
+        assert!(!screen.take_redraw());
+
+        screen.draw_pixel_at_location(3, 4);
+
+        assert!(screen.take_redraw());
+        assert!(!screen.take_redraw());
+
+        screen.clear_screen();
+
+        assert!(screen.take_redraw());
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut screen = Screen::default();
+        screen.draw_pixel_at_location(3, 4);
+
+        screen.fill();
+
+        assert_eq!(screen.how_many_ones(), SCREEN_WIDTH * SCREEN_HEIGHT);
+        assert_eq!(screen.take_dirty().len(), SCREEN_WIDTH * SCREEN_HEIGHT);
+    }
+
+    #[test]
+    fn test_invert() {
+        let mut screen = Screen::default();
+
+        screen.invert();
+
+        assert_eq!(screen.how_many_ones(), SCREEN_WIDTH * SCREEN_HEIGHT);
+
+        screen.draw_pixel_at_location(3, 4);
+        screen.invert();
+
+        assert_eq!(screen.how_many_ones(), 1);
+        assert_eq!(screen.get_pixel_color(3, 4), 1);
+    }
+
+    #[test]
+    fn test_draw_to_second_plane_only() {
+        let mut screen = Screen::default();
+
+        screen.set_active_plane(0b10);
+        screen.draw_sprite_at_location(0b1111_0000, 2, 3);
+
+        assert!(screen.screen[0].iter().all(|&row| row == 0));
+        assert!((2..6).all(|x| screen.screen[1][3] & pixel_bit(x) != 0));
+        assert_eq!(screen.how_many_ones(), 4);
+    }
+
+    #[test]
+    fn test_collision_is_per_plane() {
+        let mut screen = Screen::default();
+
+        screen.draw_sprite_at_location(0b1000_0000, 0, 0);
+
+        // plane 2 is empty at that location, so drawing there does not collide.
+        screen.set_active_plane(0b10);
+        assert!(!screen.draw_sprite_at_location(0b1000_0000, 0, 0));
+
+        // drawing on both planes collides with both pixels and clears them.
+        screen.set_active_plane(0b11);
+        assert!(screen.draw_sprite_at_location(0b1000_0000, 0, 0));
+        assert_eq!(screen.how_many_ones(), 0);
+    }
+
+    #[test]
+    fn test_clear_screen_only_clears_active_planes() {
+        let mut screen = Screen::default();
+
+        screen.set_active_plane(0b11);
+        screen.draw_pixel_at_location(1, 1);
+
+        screen.set_active_plane(0b01);
+        screen.clear_screen();
+
+        assert_eq!(screen.screen[0][1] & pixel_bit(1), 0);
+        assert_ne!(screen.screen[1][1] & pixel_bit(1), 0);
+        assert_eq!(screen.how_many_ones(), 1);
+    }
+
+    #[test]
+    fn test_classic_drawing_only_uses_colors_0_and_1() {
+        let mut screen = Screen::default();
+
+        screen.draw_sprite_at_location(0b1010_1010, 0, 0);
+        screen.draw_sprite_at_location(0b1111_0000, 0, 1);
+
+        let colors: Vec<u8> = (0..SCREEN_HEIGHT)
+            .flat_map(|y| (0..SCREEN_WIDTH).map(move |x| (x, y)))
+            .map(|(x, y)| screen.get_pixel_color(x, y))
+            .collect();
+
+        assert!(colors.iter().all(|&color| color == 0 || color == 1));
+        assert_eq!(colors.iter().filter(|&&color| color == 1).count(), 8);
+        assert_eq!(screen.get_pixel_color(0, 0), 1);
+        assert_eq!(screen.get_pixel_color(1, 0), 0);
+    }
+
+    #[test]
+    fn test_pixel_color_combines_planes() {
+        let mut screen = Screen::default();
+
+        screen.set_active_plane(0b10);
+        screen.draw_pixel_at_location(0, 0);
+        screen.set_active_plane(0b11);
+        screen.draw_pixel_at_location(1, 0);
+
+        assert_eq!(screen.get_pixel_color(0, 0), 2);
+        assert_eq!(screen.get_pixel_color(1, 0), 3);
+    }
+
+    #[test]
+    fn test_default_dimensions() {
+        let screen = Screen::default();
+
+        assert_eq!(screen.dimensions(), (64, 32));
+    }
+
+    #[test]
+    fn test_set_mode_clears_and_resizes() {
+        let mut screen = Screen::default();
+        screen.set_active_plane(0b11);
+        screen.draw_sprite_at_location(0xFF, 0, 0);
+        screen.take_dirty();
+
+        screen.set_mode(ScreenMode::Hires);
+
+        assert_eq!(screen.mode(), ScreenMode::Hires);
+        assert_eq!(screen.dimensions(), (128, 64));
+        assert_eq!(screen.how_many_ones(), 0);
+        assert_eq!(screen.take_dirty().len(), 128 * 64);
+
+        // the right half of the hires display is drawable.
+        assert!(!screen.draw_sprite_at_location(0xFF, 120, 63));
+        assert_eq!(screen.get_pixel_color(127, 63), 3);
+
+        screen.set_mode(ScreenMode::Lores);
+
+        assert_eq!(screen.dimensions(), (64, 32));
+        assert_eq!(screen.how_many_ones(), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_save_and_load_keep_hires_mode() {
+        let mut screen = Screen::default();
+        screen.set_mode(ScreenMode::Hires);
+        screen.draw_sprite_at_location(0x81, 100, 50);
+        let mut saved = Vec::new();
+        screen.save_to_writer(&mut saved).unwrap();
+
+        let mut loaded = Screen::default();
+        loaded.load_from_reader(&mut saved.as_slice()).unwrap();
+
+        assert_eq!(loaded.mode(), ScreenMode::Hires);
+        assert_eq!(loaded.get_pixel_color(100, 50), 1);
+        assert_eq!(loaded.get_pixel_color(107, 50), 1);
+        assert_eq!(loaded.how_many_ones(), 2);
+    }
+
+    #[test]
+    fn test_lores_clips_and_wraps_at_64_columns() {
+        let mut screen = Screen::default();
+
+        screen.draw_sprite_at_location(0xFF, 60, 0);
+        screen.draw_sprite_at_location_wrapped(0xFF, 60, 1);
+
+        assert_eq!(screen.how_many_ones(), 12);
+        assert_eq!(screen.get_pixel_color(3, 1), 1);
+
+        screen.fill();
+        screen.invert();
+
+        assert_eq!(screen.how_many_ones(), 0);
+        assert_eq!(format!("{screen}").len(), 65 * 32);
+    }
+
+    #[test]
+    fn test_iter_pixels() {
+        let mut screen = Screen::default();
+
+        screen.draw_pixel_at_location(5, 7);
+
+        assert_eq!(screen.iter_pixels().count(), 64 * 32);
+        assert_eq!(screen.iter_pixels().next(), Some((0, 0, false)));
+        assert_eq!(screen.iter_pixels().filter(|&(_, _, lit)| lit).collect::<Vec<_>>(), vec![(5, 7, true)]);
+    }
+
+    #[test]
+    fn test_display() {
+        let mut screen = Screen::default();
+
+        screen.draw_sprite_at_location(0b1100_0000, 0, 0);
+
+        let output = format!("{screen}");
+        let lines: Vec<&str> = output.split('\n').collect();
+
+        // 32 rows, each terminated by a newline
+        assert_eq!(lines.len(), SCREEN_HEIGHT + 1);
+        assert_eq!(lines[SCREEN_HEIGHT], "");
+        assert!(lines[..SCREEN_HEIGHT].iter().all(|line| line.len() == SCREEN_WIDTH));
+        assert!(lines[0].starts_with("##."));
+        assert_eq!(output.matches('#').count(), 2);
+    }
+
+    #[test]
+    fn test_to_pbm() {
+        let mut screen = Screen::default();
+        for (row, pixel) in [0xF0, 0x90, 0x90, 0x90, 0xF0].into_iter().enumerate() {
+            screen.draw_sprite_at_location(pixel, 10, row as u8);
+        }
+
+        let pbm = screen.to_pbm();
+        let mut lines = pbm.lines();
+
+        assert_eq!(lines.next(), Some("P1"));
+        assert_eq!(lines.next(), Some("64 32"));
+        let body: Vec<&str> = lines.collect();
+        assert_eq!(body.len(), SCREEN_HEIGHT);
+        assert!(body.iter().all(|line| line.len() == SCREEN_WIDTH));
+        assert_eq!(body.concat().matches('1').count(), screen.how_many_ones());
+        assert_eq!(&body[1][10..14], "1001");
+
+        screen.set_mode(ScreenMode::Hires);
+
+        assert!(screen.to_pbm().starts_with("P1\n128 64\n"));
+        assert_eq!(screen.to_pbm().lines().count(), 2 + 2 * HIRES_HEIGHT);
+    }
+
+    #[test]
+    fn test_packed_rows_match_per_pixel_drawing() {
+        // the previous representation: one byte per pixel, drawn bit by bit.
+        let mut reference = [[PIXEL_OFF; SCREEN_WIDTH]; SCREEN_HEIGHT];
+        let mut screen = Screen::default();
+
+        let sprites: [(u8, u8, u8); 6] = [
+            (0b1111_0000, 0, 0),
+            (0b1010_1010, 2, 0),
+            (0b1111_1111, 56, 31),
+            (0b1000_0001, 20, 10),
+            (0b1111_1111, 20, 10),
+            (0b0110_0110, 57, 31),
+        ];
+
+        for (pixel, x_coord, y_coord) in sprites {
+            let mut expected_collision = false;
+            for xline in 0..8 {
+                if pixel & (0x80 >> xline) != 0 {
+                    let x = (x_coord + xline) as usize;
+                    let cell = &mut reference[y_coord as usize][x];
+                    expected_collision |= *cell == PIXEL_ON;
+                    *cell ^= PIXEL_ON;
+                }
+            }
+
+            assert_eq!(screen.draw_sprite_at_location(pixel, x_coord, y_coord), expected_collision);
+        }
+
+        for (y, row) in reference.iter().enumerate() {
+            for (x, &pixel) in row.iter().enumerate() {
+                assert_eq!(screen.get_pixel_color(x, y), pixel);
+            }
+        }
+        let lit = reference.iter().flatten().filter(|&&pixel| pixel == PIXEL_ON).count();
+        assert_eq!(screen.how_many_ones(), lit);
+    }
+
+    #[test]
+    fn test_take_dirty_skips_pixels_that_ended_up_unchanged() {
+        let mut screen = Screen::default();
+        screen.draw_pixel_at_location(3, 4);
+        screen.take_dirty();
+
+        // (0, 4) gets drawn and erased again, (3, 4) moves to the second plane and stays lit.
+        screen.draw_sprite_at_location(0b1001_0000, 0, 4);
+        screen.draw_sprite_at_location(0b1000_0000, 0, 4);
+        screen.set_active_plane(0b10);
+        screen.draw_pixel_at_location(3, 4);
+        screen.draw_pixel_at_location(5, 4);
+
+        assert_eq!(screen.take_dirty(), vec![(5, 4, true)]);
+    }
+
+    #[test]
+    fn test_clear_screen_marks_lit_pixels_dirty() {
+        let mut screen = Screen::default();
+
+        screen.draw_pixel_at_location(3, 4);
+        screen.take_dirty();
+
+        screen.clear_screen();
+
+        assert_eq!(screen.take_dirty(), vec![(3, 4, false)]);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,42 @@
+// Logger for tests that keeps the records emitted by the current thread,
+// so tests running in parallel don't see each other's output.
+
+use std::cell::RefCell;
+use std::string::{String, ToString};
+use std::vec::Vec;
+use std::sync::Once;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+struct CapturingLogger;
+
+thread_local! {
+    static RECORDS: RefCell<Vec<(Level, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        RECORDS.with(|records| records.borrow_mut().push((record.level(), record.args().to_string())));
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger;
+static INIT: Once = Once::new();
+
+// Installs the logger (once per test binary) and clears the records of the current thread.
+pub fn start_capture() {
+    INIT.call_once(|| {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+    });
+    RECORDS.with(|records| records.borrow_mut().clear());
+}
+
+pub fn captured() -> Vec<(Level, String)> {
+    RECORDS.with(|records| records.borrow().clone())
+}